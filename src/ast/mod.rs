@@ -1,6 +1,10 @@
-use crate::token::Token;
+use crate::token::{Span, Token};
+use num_bigint::BigInt;
 use std::fmt;
 
+mod modify;
+pub use self::modify::{modify_expr, modify_program};
+
 #[derive(Debug)]
 pub struct Program {
     pub statements: Vec<Stmt>,
@@ -34,26 +38,56 @@ impl fmt::Display for Ident {
 }
 
 #[derive(Clone, PartialEq, Debug)]
-pub enum Stmt {
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Span) -> Self {
+        Stmt { kind, span }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum StmtKind {
     Let(Ident, Expr),
     Return(Expr),
     Expr(Expr),
     Block(BlockStmt),
+    While(Expr, BlockStmt), // (cond, body)
+    Break,
+    Continue,
 }
 
-impl fmt::Display for Stmt {
+impl fmt::Display for StmtKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Stmt::Let(ident, expr) => write!(f, "let {} = {}", ident, expr),
-            Stmt::Return(expr) => write!(f, "return {}", expr),
-            Stmt::Expr(expr) => write!(f, "{}", expr),
-            Stmt::Block(stmts) => {
+            StmtKind::Let(ident, expr) => write!(f, "let {} = {}", ident, expr),
+            StmtKind::Return(expr) => write!(f, "return {}", expr),
+            StmtKind::Expr(expr) => write!(f, "{}", expr),
+            StmtKind::Block(stmts) => {
                 let mut ret = Ok(());
                 for stmt in stmts {
                     ret = write!(f, "{}", stmt);
                 }
                 ret
             }
+            StmtKind::While(cond, body) => {
+                write!(f, "while ({}) {{ ", cond)?;
+                for stmt in body {
+                    write!(f, "{}; ", stmt)?;
+                }
+                write!(f, "}}")
+            }
+            StmtKind::Break => write!(f, "break"),
+            StmtKind::Continue => write!(f, "continue"),
         }
     }
 }
@@ -61,36 +95,64 @@ impl fmt::Display for Stmt {
 pub type BlockStmt = Vec<Stmt>;
 
 #[derive(Clone, PartialEq, Debug)]
-pub enum Expr {
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Expr { kind, span }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ExprKind {
     Ident(Ident),
     Literal(Literal),
     Prefix(Prefix, Box<Expr>),                   // (prefix, expr)
     Infix(Box<Expr>, Infix, Box<Expr>),          // (left, infix, right)
     If(Box<Expr>, Box<Stmt>, Option<Box<Stmt>>), // (cond, cons, alter)
     Function(Vec<Ident>, BlockStmt),             // (args, body)
+    Macro(Vec<Ident>, BlockStmt),                // (args, body)
     Call(Box<Expr>, Vec<Expr>),                  // (function, args)
+    Array(Vec<Expr>),                            // (elements)
+    Index(Box<Expr>, Box<Expr>),                 // (left, index)
+    Hash(Vec<(Expr, Expr)>),                     // (key, value) pairs
+    Assign(Ident, Box<Expr>),                    // (target, value)
 }
 
-impl fmt::Display for Expr {
+impl fmt::Display for ExprKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Expr::Ident(ident) => write!(f, "{}", ident),
-            Expr::Literal(literal) => write!(f, "{}", literal),
-            Expr::Prefix(prefix, expr) => write!(f, "({}{})", prefix, expr),
-            Expr::Infix(left, infix, right) => write!(f, "({} {} {})", left, infix, right),
-            Expr::If(cond, cons, alter) => {
+            ExprKind::Ident(ident) => write!(f, "{}", ident),
+            ExprKind::Literal(literal) => write!(f, "{}", literal),
+            ExprKind::Prefix(prefix, expr) => write!(f, "({}{})", prefix, expr),
+            ExprKind::Infix(left, infix, right) => write!(f, "({} {} {})", left, infix, right),
+            ExprKind::If(cond, cons, alter) => {
                 if let Some(box stmt) = alter {
                     write!(f, "if {} {} else {}", cond, cons, stmt)
                 } else {
                     write!(f, "if {} {}", cond, cons)
                 }
             }
-            Expr::Function(params, body) => {
+            ExprKind::Function(params, body) | ExprKind::Macro(params, body) => {
+                let keyword = match self {
+                    ExprKind::Macro(..) => "macro",
+                    _ => "fn",
+                };
                 let params_string: Vec<String> =
                     params.iter().map(|param| param.0.clone()).collect();
                 write!(
                     f,
-                    "fn ({}) {{\n {} }}",
+                    "{} ({}) {{\n {} }}",
+                    keyword,
                     params_string.join(", "),
                     body.iter()
                         .map(|stmt| format!("{}", stmt))
@@ -98,11 +160,25 @@ impl fmt::Display for Expr {
                         .join("\n")
                 )
             }
-            Expr::Call(box func, args) => {
+            ExprKind::Call(box func, args) => {
                 let params_string: Vec<String> =
                     args.iter().map(|param| format!("{}", param)).collect();
                 write!(f, "{}({})", func, params_string.join(", "))
             }
+            ExprKind::Array(elements) => {
+                let elements_string: Vec<String> =
+                    elements.iter().map(|elem| format!("{}", elem)).collect();
+                write!(f, "[{}]", elements_string.join(", "))
+            }
+            ExprKind::Index(left, index) => write!(f, "({}[{}])", left, index),
+            ExprKind::Assign(ident, value) => write!(f, "({} = {})", ident, value),
+            ExprKind::Hash(pairs) => {
+                let pairs_string: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, val)| format!("{}: {}", key, val))
+                    .collect();
+                write!(f, "{{{}}}", pairs_string.join(", "))
+            }
         }
     }
 }
@@ -110,14 +186,20 @@ impl fmt::Display for Expr {
 #[derive(PartialEq, Clone, Debug)]
 pub enum Literal {
     Int(i64),
+    BigInt(BigInt),
+    Float(f64),
     Bool(bool),
+    Str(String),
 }
 
 impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Literal::Int(val) => write!(f, "{}", val),
+            Literal::BigInt(val) => write!(f, "{}", val),
+            Literal::Float(val) => write!(f, "{:?}", val),
             Literal::Bool(val) => write!(f, "{}", val),
+            Literal::Str(val) => write!(f, "\"{}\"", val),
         }
     }
 }
@@ -156,10 +238,16 @@ pub enum Infix {
     Minus,
     Divide,
     Multiply,
+    Modulo,
+    Power,
     Equal,
     NotEqual,
     GreaterThan,
     LessThan,
+    GreaterOrEqual,
+    LessOrEqual,
+    And,
+    Or,
 }
 
 impl Infix {
@@ -169,10 +257,16 @@ impl Infix {
             Token::MINUS => Ok(Infix::Minus),
             Token::SLASH => Ok(Infix::Divide),
             Token::ASTERISK => Ok(Infix::Multiply),
+            Token::PERCENT => Ok(Infix::Modulo),
+            Token::POWER => Ok(Infix::Power),
             Token::EQ => Ok(Infix::Equal),
             Token::NOTEQ => Ok(Infix::NotEqual),
             Token::GT => Ok(Infix::GreaterThan),
             Token::LT => Ok(Infix::LessThan),
+            Token::GTEQ => Ok(Infix::GreaterOrEqual),
+            Token::LTEQ => Ok(Infix::LessOrEqual),
+            Token::AND => Ok(Infix::And),
+            Token::OR => Ok(Infix::Or),
             _ => Err(format!("this is not prefix token. got {:?}", tok)),
         }
     }
@@ -185,10 +279,16 @@ impl fmt::Display for Infix {
             Infix::Minus => write!(f, "-"),
             Infix::Divide => write!(f, "/"),
             Infix::Multiply => write!(f, "*"),
+            Infix::Modulo => write!(f, "%"),
+            Infix::Power => write!(f, "**"),
             Infix::Equal => write!(f, "=="),
             Infix::NotEqual => write!(f, "!="),
             Infix::GreaterThan => write!(f, ">"),
             Infix::LessThan => write!(f, "<"),
+            Infix::GreaterOrEqual => write!(f, ">="),
+            Infix::LessOrEqual => write!(f, "<="),
+            Infix::And => write!(f, "&&"),
+            Infix::Or => write!(f, "||"),
         }
     }
 }
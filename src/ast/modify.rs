@@ -0,0 +1,97 @@
+use super::*;
+
+/// rewrite every expression in the program with `modifier`, children first
+pub fn modify_program<E, F>(program: Program, modifier: &mut F) -> Result<Program, E>
+where
+    F: FnMut(Expr) -> Result<Expr, E>,
+{
+    Ok(Program {
+        statements: modify_stmts(program.statements, modifier)?,
+    })
+}
+
+fn modify_stmts<E, F>(stmts: Vec<Stmt>, modifier: &mut F) -> Result<Vec<Stmt>, E>
+where
+    F: FnMut(Expr) -> Result<Expr, E>,
+{
+    stmts
+        .into_iter()
+        .map(|stmt| modify_stmt(stmt, modifier))
+        .collect()
+}
+
+fn modify_stmt<E, F>(stmt: Stmt, modifier: &mut F) -> Result<Stmt, E>
+where
+    F: FnMut(Expr) -> Result<Expr, E>,
+{
+    let kind = match stmt.kind {
+        StmtKind::Let(ident, expr) => StmtKind::Let(ident, modify_expr(expr, modifier)?),
+        StmtKind::Return(expr) => StmtKind::Return(modify_expr(expr, modifier)?),
+        StmtKind::Expr(expr) => StmtKind::Expr(modify_expr(expr, modifier)?),
+        StmtKind::Block(stmts) => StmtKind::Block(modify_stmts(stmts, modifier)?),
+        StmtKind::While(cond, body) => {
+            StmtKind::While(modify_expr(cond, modifier)?, modify_stmts(body, modifier)?)
+        }
+        kind @ (StmtKind::Break | StmtKind::Continue) => kind,
+    };
+    Ok(Stmt::new(kind, stmt.span))
+}
+
+/// rewrite the expression and all expressions in it with `modifier`, children first
+pub fn modify_expr<E, F>(expr: Expr, modifier: &mut F) -> Result<Expr, E>
+where
+    F: FnMut(Expr) -> Result<Expr, E>,
+{
+    let kind = match expr.kind {
+        ExprKind::Prefix(prefix, right) => {
+            ExprKind::Prefix(prefix, Box::new(modify_expr(*right, modifier)?))
+        }
+        ExprKind::Infix(left, infix, right) => ExprKind::Infix(
+            Box::new(modify_expr(*left, modifier)?),
+            infix,
+            Box::new(modify_expr(*right, modifier)?),
+        ),
+        ExprKind::If(cond, cons, alt) => ExprKind::If(
+            Box::new(modify_expr(*cond, modifier)?),
+            Box::new(modify_stmt(*cons, modifier)?),
+            match alt {
+                Some(alt) => Some(Box::new(modify_stmt(*alt, modifier)?)),
+                None => None,
+            },
+        ),
+        ExprKind::Function(params, body) => {
+            ExprKind::Function(params, modify_stmts(body, modifier)?)
+        }
+        ExprKind::Macro(params, body) => ExprKind::Macro(params, modify_stmts(body, modifier)?),
+        ExprKind::Call(func, args) => ExprKind::Call(
+            Box::new(modify_expr(*func, modifier)?),
+            modify_exprs(args, modifier)?,
+        ),
+        ExprKind::Array(elements) => ExprKind::Array(modify_exprs(elements, modifier)?),
+        ExprKind::Index(left, index) => ExprKind::Index(
+            Box::new(modify_expr(*left, modifier)?),
+            Box::new(modify_expr(*index, modifier)?),
+        ),
+        ExprKind::Hash(pairs) => ExprKind::Hash(
+            pairs
+                .into_iter()
+                .map(|(key, val)| Ok((modify_expr(key, modifier)?, modify_expr(val, modifier)?)))
+                .collect::<Result<_, E>>()?,
+        ),
+        ExprKind::Assign(ident, value) => {
+            ExprKind::Assign(ident, Box::new(modify_expr(*value, modifier)?))
+        }
+        kind @ ExprKind::Ident(_) | kind @ ExprKind::Literal(_) => kind,
+    };
+    modifier(Expr::new(kind, expr.span))
+}
+
+fn modify_exprs<E, F>(exprs: Vec<Expr>, modifier: &mut F) -> Result<Vec<Expr>, E>
+where
+    F: FnMut(Expr) -> Result<Expr, E>,
+{
+    exprs
+        .into_iter()
+        .map(|expr| modify_expr(expr, modifier))
+        .collect()
+}
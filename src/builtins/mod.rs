@@ -0,0 +1,62 @@
+use crate::object::{Builtin, EvalError, EvalResult, Object};
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "len",
+        func: len,
+    },
+    Builtin {
+        name: "puts",
+        func: puts,
+    },
+    Builtin {
+        name: "type",
+        func: type_of,
+    },
+];
+
+/// find builtin function by name
+pub fn lookup(name: &str) -> Option<Object> {
+    BUILTINS
+        .iter()
+        .find(|builtin| builtin.name == name)
+        .map(|builtin| Object::Builtin(builtin.clone()))
+}
+
+fn check_args_len(args: &[Object], want: usize) -> EvalResult<()> {
+    if args.len() == want {
+        Ok(())
+    } else {
+        Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want={}",
+            args.len(),
+            want
+        )))
+    }
+}
+
+fn len(args: Vec<Object>) -> EvalResult<Object> {
+    check_args_len(&args, 1)?;
+
+    match &args[0] {
+        Object::Str(v) => Ok(Object::Int(v.chars().count() as i64)),
+        Object::Array(elements) => Ok(Object::Int(elements.len() as i64)),
+        Object::Hash(pairs) => Ok(Object::Int(pairs.len() as i64)),
+        obj => Err(EvalError::new(format!(
+            "argument to `len` not supported, got {}",
+            obj.get_type()
+        ))),
+    }
+}
+
+fn puts(args: Vec<Object>) -> EvalResult<Object> {
+    for arg in args {
+        println!("{}", arg);
+    }
+    Ok(Object::Null)
+}
+
+fn type_of(args: Vec<Object>) -> EvalResult<Object> {
+    check_args_len(&args, 1)?;
+    Ok(Object::Str(args[0].get_type()))
+}
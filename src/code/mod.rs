@@ -0,0 +1,214 @@
+use crate::token::Span;
+
+#[cfg(test)]
+mod test;
+
+pub type Instructions = Vec<u8>;
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Opcode {
+    Constant,
+    Pop,
+    True,
+    False,
+    Null,
+
+    // infix operators
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    LessThan,
+    Mod,
+    Pow,
+    GreaterEqual,
+    LessEqual,
+
+    // prefix operators
+    Minus,
+    Bang,
+    UnaryPlus,
+
+    Jump,
+    JumpNotTruthy,
+
+    GetGlobal,
+    SetGlobal,
+    AssignGlobal, // like `SetGlobal`, but only to a variable already defined
+    GetLocal,
+    SetLocal,
+    GetFree,
+    SetFree,
+    CurrentClosure,
+    // push a variable for a closure to capture, keeping it in a cell shared with the closure
+    CaptureLocal,
+    CaptureFree,
+    MakeCell, // move a local variable into a cell, so that closures see assignments to it
+
+    Array,
+    Hash,
+    Index,
+
+    Call,
+    ReturnValue,
+    Closure,
+    Quote,
+}
+
+impl Opcode {
+    /// opcode with the largest byte. keep it the last variant when adding one
+    #[cfg(test)]
+    pub const LAST: Opcode = Opcode::Quote;
+
+    pub fn from_byte(byte: u8) -> Opcode {
+        match byte {
+            0 => Opcode::Constant,
+            1 => Opcode::Pop,
+            2 => Opcode::True,
+            3 => Opcode::False,
+            4 => Opcode::Null,
+            5 => Opcode::Add,
+            6 => Opcode::Sub,
+            7 => Opcode::Mul,
+            8 => Opcode::Div,
+            9 => Opcode::Equal,
+            10 => Opcode::NotEqual,
+            11 => Opcode::GreaterThan,
+            12 => Opcode::LessThan,
+            13 => Opcode::Mod,
+            14 => Opcode::Pow,
+            15 => Opcode::GreaterEqual,
+            16 => Opcode::LessEqual,
+            17 => Opcode::Minus,
+            18 => Opcode::Bang,
+            19 => Opcode::UnaryPlus,
+            20 => Opcode::Jump,
+            21 => Opcode::JumpNotTruthy,
+            22 => Opcode::GetGlobal,
+            23 => Opcode::SetGlobal,
+            24 => Opcode::AssignGlobal,
+            25 => Opcode::GetLocal,
+            26 => Opcode::SetLocal,
+            27 => Opcode::GetFree,
+            28 => Opcode::SetFree,
+            29 => Opcode::CurrentClosure,
+            30 => Opcode::CaptureLocal,
+            31 => Opcode::CaptureFree,
+            32 => Opcode::MakeCell,
+            33 => Opcode::Array,
+            34 => Opcode::Hash,
+            35 => Opcode::Index,
+            36 => Opcode::Call,
+            37 => Opcode::ReturnValue,
+            38 => Opcode::Closure,
+            39 => Opcode::Quote,
+            _ => panic!("unknown opcode: {}", byte),
+        }
+    }
+
+    /// byte width of each operand
+    pub fn operand_widths(self) -> &'static [usize] {
+        match self {
+            Opcode::Constant
+            | Opcode::Jump
+            | Opcode::JumpNotTruthy
+            | Opcode::GetGlobal
+            | Opcode::SetGlobal
+            | Opcode::AssignGlobal
+            | Opcode::Array
+            | Opcode::Hash => &[2],
+            Opcode::GetLocal
+            | Opcode::SetLocal
+            | Opcode::GetFree
+            | Opcode::SetFree
+            | Opcode::CaptureLocal
+            | Opcode::CaptureFree
+            | Opcode::MakeCell
+            | Opcode::Call => &[1],
+            Opcode::Closure => &[2, 1], // (constant index, number of free variables)
+            Opcode::Quote => &[2, 1],   // (constant index, number of unquoted values)
+            _ => &[],
+        }
+    }
+}
+
+/// encode an instruction. operands are written in big endian
+pub fn make(op: Opcode, operands: &[usize]) -> Instructions {
+    let widths = op.operand_widths();
+    let mut ins = vec![op as u8];
+
+    for (operand, width) in operands.iter().zip(widths) {
+        match width {
+            2 => ins.extend_from_slice(&(*operand as u16).to_be_bytes()),
+            1 => ins.push(*operand as u8),
+            _ => unreachable!(),
+        }
+    }
+
+    ins
+}
+
+/// decode operands following an opcode. returns operands and read byte length
+#[cfg(test)]
+pub fn read_operands(op: Opcode, ins: &[u8]) -> (Vec<usize>, usize) {
+    let mut operands = Vec::new();
+    let mut offset = 0;
+
+    for width in op.operand_widths() {
+        match width {
+            2 => operands.push(read_u16(&ins[offset..])),
+            1 => operands.push(ins[offset] as usize),
+            _ => unreachable!(),
+        }
+        offset += width;
+    }
+
+    (operands, offset)
+}
+
+pub fn read_u16(ins: &[u8]) -> usize {
+    u16::from_be_bytes([ins[0], ins[1]]) as usize
+}
+
+#[cfg(test)]
+pub fn disassemble(ins: &[u8]) -> String {
+    let mut out = String::new();
+    let mut pos = 0;
+
+    while pos < ins.len() {
+        let op = Opcode::from_byte(ins[pos]);
+        let (operands, read) = read_operands(op, &ins[pos + 1..]);
+        let operands: Vec<String> = operands.iter().map(|o| o.to_string()).collect();
+
+        out.push_str(&format!("{:04} {:?}", pos, op));
+        if !operands.is_empty() {
+            out.push_str(&format!(" {}", operands.join(" ")));
+        }
+        out.push('\n');
+
+        pos += 1 + read;
+    }
+
+    out
+}
+
+/// locations of the instructions that may fail at runtime, ordered by offset
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SourceMap(Vec<(usize, Span)>);
+
+impl SourceMap {
+    pub fn add(&mut self, pos: usize, span: Span) {
+        self.0.push((pos, span))
+    }
+
+    pub fn lookup(&self, pos: usize) -> Option<Span> {
+        self.0
+            .binary_search_by_key(&pos, |(p, _)| *p)
+            .ok()
+            .map(|idx| self.0[idx].1)
+    }
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+#[test]
+fn test_make() {
+    let test_cases = vec![
+        (
+            Opcode::Constant,
+            vec![65534],
+            vec![Opcode::Constant as u8, 255, 254],
+        ),
+        (Opcode::Add, vec![], vec![Opcode::Add as u8]),
+        (
+            Opcode::GetLocal,
+            vec![255],
+            vec![Opcode::GetLocal as u8, 255],
+        ),
+        (
+            Opcode::Closure,
+            vec![65534, 255],
+            vec![Opcode::Closure as u8, 255, 254, 255],
+        ),
+    ];
+
+    for (op, operands, expect) in test_cases {
+        assert_eq!(make(op, &operands), expect);
+    }
+}
+
+#[test]
+fn test_read_operands() {
+    let test_cases = vec![
+        (Opcode::Constant, vec![65535], 2),
+        (Opcode::GetLocal, vec![255], 1),
+        (Opcode::Closure, vec![65535, 255], 3),
+    ];
+
+    for (op, operands, bytes_read) in test_cases {
+        let ins = make(op, &operands);
+        let (actual, read) = read_operands(op, &ins[1..]);
+
+        assert_eq!(read, bytes_read);
+        assert_eq!(actual, operands);
+    }
+}
+
+#[test]
+fn test_disassemble() {
+    let ins = [
+        make(Opcode::Add, &[]),
+        make(Opcode::GetLocal, &[1]),
+        make(Opcode::Constant, &[2]),
+        make(Opcode::Constant, &[65535]),
+        make(Opcode::Closure, &[65535, 255]),
+    ]
+    .concat();
+
+    let expect = "0000 Add
+0001 GetLocal 1
+0003 Constant 2
+0006 Constant 65535
+0009 Closure 65535 255
+";
+
+    assert_eq!(disassemble(&ins), expect);
+}
+
+#[test]
+fn test_opcode_from_byte() {
+    for byte in 0..=(Opcode::LAST as u8) {
+        assert_eq!(Opcode::from_byte(byte) as u8, byte);
+    }
+}
+
+#[test]
+#[should_panic(expected = "unknown opcode")]
+fn test_opcode_from_byte_after_last() {
+    Opcode::from_byte(Opcode::LAST as u8 + 1);
+}
@@ -0,0 +1,684 @@
+use crate::ast::{
+    modify_program, Expr, ExprKind, Ident, Infix, Literal, Prefix, Program, Stmt, StmtKind,
+};
+use crate::code::{self, Instructions, Opcode, SourceMap};
+use crate::evaluator::{outside_loop_error, quote};
+use crate::object::{function_repr, CompiledFunction, Env, EvalError, EvalResult, Func, Object};
+use crate::token::Span;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+mod symbol_table;
+pub use self::symbol_table::{Symbol, SymbolScope, SymbolTable};
+
+#[cfg(test)]
+mod test;
+
+/// compiled program passed to `crate::vm::Vm`
+#[derive(Debug)]
+pub struct Bytecode {
+    pub instructions: Instructions,
+    pub spans: SourceMap,
+    pub constants: Vec<Object>,
+    pub global_names: Vec<String>,
+}
+
+#[derive(Default)]
+struct CompilationScope {
+    instructions: Instructions,
+    spans: SourceMap,
+    last_instruction: Option<(Opcode, usize)>,
+    cells: HashSet<String>, // local variables kept in cells (see `Opcode::MakeCell`)
+    loops: Vec<Loop>,       // loops around the code being compiled, innermost last
+}
+
+struct Loop {
+    start: usize,       // position of the condition, where `continue` jumps
+    breaks: Vec<usize>, // positions of jumps to patch with the end of the loop
+}
+
+/// compiles programs into bytecode. constants and global bindings are kept
+/// across calls of `compile` so it can be fed line by line from the REPL
+pub struct Compiler {
+    constants: Vec<Object>,
+    symbol_table: SymbolTable,
+    scopes: Vec<CompilationScope>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Compiler::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            constants: Vec::new(),
+            symbol_table: SymbolTable::new(),
+            scopes: vec![CompilationScope::default()],
+        }
+    }
+
+    pub fn compile(&mut self, program: &Program) -> EvalResult<Bytecode> {
+        self.scopes = vec![CompilationScope::default()];
+
+        for stmt in &program.statements {
+            self.compile_stmt(stmt)?;
+        }
+
+        // result of the program is the last popped value
+        match program.statements.last() {
+            Some(Stmt {
+                kind: StmtKind::Expr(_),
+                ..
+            })
+            | None => {}
+            Some(_) => {
+                self.emit(Opcode::Null, &[])?;
+                self.emit(Opcode::Pop, &[])?;
+            }
+        }
+
+        let scope = self.scopes.pop().expect("no compilation scope");
+        Ok(Bytecode {
+            instructions: scope.instructions,
+            spans: scope.spans,
+            constants: self.constants.clone(),
+            global_names: self.symbol_table.global_names(),
+        })
+    }
+
+    fn compile_stmt(&mut self, stmt: &Stmt) -> EvalResult<()> {
+        match &stmt.kind {
+            StmtKind::Expr(expr) => {
+                self.compile_expr(expr)?;
+                self.emit(Opcode::Pop, &[])?;
+            }
+            StmtKind::Return(expr) => {
+                self.compile_expr(expr)?;
+                self.emit(Opcode::ReturnValue, &[])?;
+            }
+            StmtKind::Let(ident, expr) => {
+                let is_cell = self.is_cell(&ident.0);
+                match &expr.kind {
+                    // the function may assign to its own name. the cell is made
+                    // first so that it captures the variable rather than itself
+                    ExprKind::Function(params, body) if is_cell => {
+                        let symbol = self.symbol_table.define(&ident.0);
+                        self.emit(Opcode::MakeCell, &[symbol.index])?;
+                        self.compile_function(Some(ident), params, body)?
+                    }
+                    ExprKind::Function(params, body) => {
+                        self.compile_function(Some(ident), params, body)?
+                    }
+                    _ => self.compile_expr(expr)?,
+                }
+                let symbol = self.symbol_table.define(&ident.0);
+                match symbol.scope {
+                    SymbolScope::Global => self.emit(Opcode::SetGlobal, &[symbol.index])?,
+                    _ => self.emit(Opcode::SetLocal, &[symbol.index])?,
+                };
+                if is_cell {
+                    self.emit(Opcode::MakeCell, &[symbol.index])?;
+                }
+            }
+            StmtKind::Block(stmts) => self.compile_block_value(stmts)?,
+            StmtKind::While(cond, body) => self.compile_while(cond, body)?,
+            StmtKind::Break => {
+                let jump = self.emit(Opcode::Jump, &[0])?;
+                match self.current_scope().loops.last_mut() {
+                    Some(innermost) => innermost.breaks.push(jump),
+                    None => return Err(outside_loop_error("break").or_span(stmt.span)),
+                }
+            }
+            StmtKind::Continue => match self.current_scope().loops.last() {
+                Some(innermost) => {
+                    let start = innermost.start;
+                    self.emit(Opcode::Jump, &[start])?;
+                }
+                None => return Err(outside_loop_error("continue").or_span(stmt.span)),
+            },
+        }
+        Ok(())
+    }
+
+    fn compile_while(&mut self, cond: &Expr, body: &[Stmt]) -> EvalResult<()> {
+        let start = self.current_instructions().len();
+        self.compile_expr(cond)?;
+        let jump_not_truthy = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+        self.current_scope().loops.push(Loop {
+            start,
+            breaks: Vec::new(),
+        });
+        for stmt in body {
+            self.compile_stmt(stmt)?;
+        }
+        let innermost = self.current_scope().loops.pop().expect("no loop");
+        self.emit(Opcode::Jump, &[start])?;
+
+        let end = self.current_instructions().len();
+        self.change_operand(jump_not_truthy, end)?;
+        for jump in innermost.breaks {
+            self.change_operand(jump, end)?;
+        }
+        Ok(())
+    }
+
+    /// whether the variable bound in the current function is kept in a cell.
+    /// besides assigned ones, variables bound in loops are, so closures see the
+    /// binding of the last iteration like in the evaluator, where they share the scope
+    fn is_cell(&mut self, name: &str) -> bool {
+        let is_local = self.scopes.len() > 1;
+        let scope = self.current_scope();
+        is_local && (scope.cells.contains(name) || !scope.loops.is_empty())
+    }
+
+    /// compiles statements leaving the value of the block on the stack
+    fn compile_block_value(&mut self, stmts: &[Stmt]) -> EvalResult<()> {
+        for stmt in stmts {
+            self.compile_stmt(stmt)?;
+        }
+
+        match stmts.last() {
+            Some(Stmt {
+                kind: StmtKind::Expr(_),
+                ..
+            }) => self.remove_last_pop(),
+            Some(Stmt {
+                kind: StmtKind::Block(_),
+                ..
+            }) => {}
+            _ => {
+                self.emit(Opcode::Null, &[])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_expr(&mut self, expr: &Expr) -> EvalResult<()> {
+        let span = expr.span;
+        match &expr.kind {
+            ExprKind::Literal(Literal::Int(val)) => {
+                let idx = self.add_constant(Object::Int(*val));
+                self.emit(Opcode::Constant, &[idx])?;
+            }
+            ExprKind::Literal(Literal::BigInt(val)) => {
+                let idx = self.add_constant(Object::BigInt(val.clone()));
+                self.emit(Opcode::Constant, &[idx])?;
+            }
+            ExprKind::Literal(Literal::Float(val)) => {
+                let idx = self.add_constant(Object::Float(*val));
+                self.emit(Opcode::Constant, &[idx])?;
+            }
+            ExprKind::Literal(Literal::Str(val)) => {
+                let idx = self.add_constant(Object::Str(val.clone()));
+                self.emit(Opcode::Constant, &[idx])?;
+            }
+            ExprKind::Literal(Literal::Bool(true)) => {
+                self.emit(Opcode::True, &[])?;
+            }
+            ExprKind::Literal(Literal::Bool(false)) => {
+                self.emit(Opcode::False, &[])?;
+            }
+            ExprKind::Prefix(prefix, right) => {
+                self.compile_expr(right)?;
+                let op = match prefix {
+                    Prefix::Plus => Opcode::UnaryPlus,
+                    Prefix::Minus => Opcode::Minus,
+                    Prefix::Not => Opcode::Bang,
+                };
+                self.emit_at(op, &[], span)?;
+            }
+            ExprKind::Infix(left, infix @ Infix::And, right)
+            | ExprKind::Infix(left, infix @ Infix::Or, right) => {
+                self.compile_logical_expr(left, infix, right)?
+            }
+            ExprKind::Infix(left, infix, right) => {
+                self.compile_expr(left)?;
+                self.compile_expr(right)?;
+                let op = match infix {
+                    Infix::Plus => Opcode::Add,
+                    Infix::Minus => Opcode::Sub,
+                    Infix::Multiply => Opcode::Mul,
+                    Infix::Divide => Opcode::Div,
+                    Infix::Equal => Opcode::Equal,
+                    Infix::NotEqual => Opcode::NotEqual,
+                    Infix::GreaterThan => Opcode::GreaterThan,
+                    Infix::LessThan => Opcode::LessThan,
+                    Infix::Modulo => Opcode::Mod,
+                    Infix::Power => Opcode::Pow,
+                    Infix::GreaterOrEqual => Opcode::GreaterEqual,
+                    Infix::LessOrEqual => Opcode::LessEqual,
+                    Infix::And | Infix::Or => unreachable!("logical operators short-circuit"),
+                };
+                self.emit_at(op, &[], span)?;
+            }
+            ExprKind::If(cond, cons, alt) => {
+                self.compile_expr(cond)?;
+                let jump_not_truthy = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+                self.compile_block_value(std::slice::from_ref(cons))?;
+                let jump = self.emit(Opcode::Jump, &[0])?;
+
+                let after_cons = self.current_instructions().len();
+                self.change_operand(jump_not_truthy, after_cons)?;
+
+                match alt {
+                    Some(alt) => self.compile_block_value(std::slice::from_ref(alt))?,
+                    None => {
+                        self.emit(Opcode::Null, &[])?;
+                    }
+                }
+
+                let after_alt = self.current_instructions().len();
+                self.change_operand(jump, after_alt)?;
+            }
+            ExprKind::Ident(ident) => {
+                let symbol = match self.symbol_table.resolve(&ident.0) {
+                    Some(symbol) => symbol,
+                    // bound later or a builtin. looked up when executed
+                    None => self.symbol_table.define_global(&ident.0),
+                };
+                self.load_symbol(&symbol, span)?;
+            }
+            ExprKind::Function(params, body) => self.compile_function(None, params, body)?,
+            // macros are expanded before compilation. a remaining literal is just a value
+            ExprKind::Macro(params, body) => {
+                let mac = Object::Macro(Func {
+                    name: None,
+                    args: params.clone(),
+                    body: body.clone(),
+                    env: Env::new(),
+                });
+                let idx = self.add_constant(mac);
+                self.emit(Opcode::Constant, &[idx])?;
+            }
+            ExprKind::Call(func, args) if quote::is_quote(func) => {
+                if args.len() != 1 {
+                    return Err(EvalError::new(format!(
+                        "wrong number of arguments. got={}, want=1",
+                        args.len()
+                    ))
+                    .or_span(span));
+                }
+                self.compile_quote(&args[0])?;
+            }
+            ExprKind::Call(func, args) => {
+                self.compile_expr(func)?;
+                for arg in args {
+                    self.compile_expr(arg)?;
+                }
+                self.emit_at(Opcode::Call, &[args.len()], span)?;
+            }
+            ExprKind::Array(elements) => {
+                for elem in elements {
+                    self.compile_expr(elem)?;
+                }
+                self.emit(Opcode::Array, &[elements.len()])?;
+            }
+            ExprKind::Hash(pairs) => {
+                for (key, val) in pairs {
+                    self.compile_expr(key)?;
+                    self.compile_expr(val)?;
+                }
+                self.emit_at(Opcode::Hash, &[pairs.len() * 2], span)?;
+            }
+            ExprKind::Index(left, index) => {
+                self.compile_expr(left)?;
+                self.compile_expr(index)?;
+                self.emit_at(Opcode::Index, &[], span)?;
+            }
+            ExprKind::Assign(ident, value) => {
+                self.compile_expr(value)?;
+                let symbol = match self.symbol_table.resolve(&ident.0) {
+                    Some(symbol) => symbol,
+                    // may be defined later. checked when executed
+                    None => self.symbol_table.define_global(&ident.0),
+                };
+                match symbol.scope {
+                    SymbolScope::Global => {
+                        self.emit_at(Opcode::AssignGlobal, &[symbol.index], span)?
+                    }
+                    SymbolScope::Local => self.emit(Opcode::SetLocal, &[symbol.index])?,
+                    SymbolScope::Free => self.emit(Opcode::SetFree, &[symbol.index])?,
+                    SymbolScope::Function => {
+                        unreachable!("functions assigning to their name do not refer to themselves")
+                    }
+                };
+                // the value of the assignment
+                self.load_symbol(&symbol, span)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// jumps over right operand when left one decides the result
+    fn compile_logical_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Infix,
+        right: &Expr,
+    ) -> EvalResult<()> {
+        self.compile_expr(left)?;
+        let jump_not_truthy = self.emit(Opcode::JumpNotTruthy, &[0])?;
+
+        if *operator == Infix::Or {
+            self.emit(Opcode::True, &[])?;
+        } else {
+            self.compile_right_operand(right)?;
+        }
+        let jump = self.emit(Opcode::Jump, &[0])?;
+
+        let after_left = self.current_instructions().len();
+        self.change_operand(jump_not_truthy, after_left)?;
+
+        if *operator == Infix::Or {
+            self.compile_right_operand(right)?;
+        } else {
+            self.emit(Opcode::False, &[])?;
+        }
+
+        let after_right = self.current_instructions().len();
+        self.change_operand(jump, after_right)?;
+        Ok(())
+    }
+
+    /// right operand of logical operator, converted to boolean
+    fn compile_right_operand(&mut self, right: &Expr) -> EvalResult<()> {
+        self.compile_expr(right)?;
+        self.emit(Opcode::Bang, &[])?;
+        self.emit(Opcode::Bang, &[])?;
+        Ok(())
+    }
+
+    /// pushes values of unquoted expressions, which `Opcode::Quote` splices into the template
+    fn compile_quote(&mut self, expr: &Expr) -> EvalResult<()> {
+        let unquoted = quote::unquoted_args(expr);
+        for arg in &unquoted {
+            self.compile_expr(arg)?;
+        }
+
+        let idx = self.add_constant(Object::Quote(expr.clone()));
+        self.emit(Opcode::Quote, &[idx, unquoted.len()])?;
+        Ok(())
+    }
+
+    fn compile_function(
+        &mut self,
+        name: Option<&Ident>,
+        params: &[Ident],
+        body: &[Stmt],
+    ) -> EvalResult<()> {
+        self.enter_scope();
+        // the scope is left on errors too, as the compiler is reused by the REPL
+        let result = self.compile_function_body(name, params, body);
+        let (scope, symbol_table) = self.leave_scope();
+        result?;
+
+        for symbol in &symbol_table.free_symbols {
+            self.capture_symbol(symbol)?;
+        }
+
+        let func = CompiledFunction {
+            name: name.map(|name| name.0.clone()),
+            instructions: scope.instructions,
+            spans: scope.spans,
+            num_locals: symbol_table.num_definitions,
+            num_params: params.len(),
+            repr: function_repr("fn", params, body),
+        };
+        let idx = self.add_constant(Object::CompiledFunction(Rc::new(func)));
+        self.emit(Opcode::Closure, &[idx, symbol_table.free_symbols.len()])?;
+        Ok(())
+    }
+
+    fn compile_function_body(
+        &mut self,
+        name: Option<&Ident>,
+        params: &[Ident],
+        body: &[Stmt],
+    ) -> EvalResult<()> {
+        let cells = assigned_names(body);
+
+        if let Some(name) = name {
+            // otherwise the name refers to the variable the function is assigned to
+            if !cells.contains(&name.0) {
+                self.symbol_table.define_function_name(&name.0);
+            }
+        }
+        for param in params {
+            let symbol = self.symbol_table.define(&param.0);
+            if cells.contains(&param.0) {
+                self.emit(Opcode::MakeCell, &[symbol.index])?;
+            }
+        }
+        // the cells are captured before the `let` is run and filled by it
+        for name in forward_refs(params, body) {
+            let symbol = self.symbol_table.define(&name);
+            self.emit(Opcode::MakeCell, &[symbol.index])?;
+        }
+        self.current_scope().cells = cells;
+
+        self.compile_block_value(body)?;
+        self.emit(Opcode::ReturnValue, &[])?;
+        Ok(())
+    }
+
+    fn load_symbol(&mut self, symbol: &Symbol, span: Span) -> EvalResult<()> {
+        match symbol.scope {
+            SymbolScope::Global => self.emit_at(Opcode::GetGlobal, &[symbol.index], span)?,
+            SymbolScope::Local => self.emit(Opcode::GetLocal, &[symbol.index])?,
+            SymbolScope::Free => self.emit(Opcode::GetFree, &[symbol.index])?,
+            SymbolScope::Function => self.emit(Opcode::CurrentClosure, &[])?,
+        };
+        Ok(())
+    }
+
+    /// like `load_symbol`, but keeps the cell of a variable for a closure
+    fn capture_symbol(&mut self, symbol: &Symbol) -> EvalResult<()> {
+        match symbol.scope {
+            SymbolScope::Local => self.emit(Opcode::CaptureLocal, &[symbol.index])?,
+            SymbolScope::Free => self.emit(Opcode::CaptureFree, &[symbol.index])?,
+            _ => return self.load_symbol(symbol, Span::default()),
+        };
+        Ok(())
+    }
+
+    fn add_constant(&mut self, obj: Object) -> usize {
+        self.constants.push(obj);
+        self.constants.len() - 1
+    }
+
+    /// appends an instruction and returns its position
+    fn emit(&mut self, op: Opcode, operands: &[usize]) -> EvalResult<usize> {
+        check_operands(op, operands)?;
+        let scope = self.current_scope();
+        let pos = scope.instructions.len();
+        scope.instructions.extend(code::make(op, operands));
+        scope.last_instruction = Some((op, pos));
+        Ok(pos)
+    }
+
+    /// appends an instruction which may fail at runtime, recording its location
+    fn emit_at(&mut self, op: Opcode, operands: &[usize], span: Span) -> EvalResult<usize> {
+        let pos = self.emit(op, operands)?;
+        self.current_scope().spans.add(pos, span);
+        Ok(pos)
+    }
+
+    fn remove_last_pop(&mut self) {
+        let scope = self.current_scope();
+        if let Some((Opcode::Pop, pos)) = scope.last_instruction {
+            scope.instructions.truncate(pos);
+            scope.last_instruction = None;
+        }
+    }
+
+    fn change_operand(&mut self, pos: usize, operand: usize) -> EvalResult<()> {
+        let ins = &mut self.current_scope().instructions;
+        let op = Opcode::from_byte(ins[pos]);
+        check_operands(op, &[operand])?;
+        let new = code::make(op, &[operand]);
+        ins[pos..pos + new.len()].copy_from_slice(&new);
+        Ok(())
+    }
+
+    fn current_scope(&mut self) -> &mut CompilationScope {
+        self.scopes.last_mut().expect("no compilation scope")
+    }
+
+    fn current_instructions(&mut self) -> &Instructions {
+        &self.current_scope().instructions
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(CompilationScope::default());
+        self.symbol_table.enter();
+    }
+
+    fn leave_scope(&mut self) -> (CompilationScope, SymbolTable) {
+        let scope = self.scopes.pop().expect("no compilation scope");
+        (scope, self.symbol_table.leave())
+    }
+}
+
+/// fails if an operand does not fit in its width, e.g. a jump over a large body
+fn check_operands(op: Opcode, operands: &[usize]) -> EvalResult<()> {
+    for (operand, width) in operands.iter().zip(op.operand_widths()) {
+        if *operand >= 1 << (width * 8) {
+            return Err(EvalError::new(format!(
+                "too many operands for {:?}: {}",
+                op, operand
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// names of variables assigned in the function body, including in functions nested in it
+fn assigned_names(body: &[Stmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let program = Program {
+        statements: body.to_vec(),
+    };
+    let _ = modify_program(program, &mut |expr: Expr| -> Result<Expr, !> {
+        if let ExprKind::Assign(ident, _) = &expr.kind {
+            names.insert(ident.0.clone());
+        }
+        Ok(expr)
+    });
+    names
+}
+
+/// variables bound by `let` in the function body which functions nested in it
+/// use before the `let`, e.g. mutually recursive functions. they are defined
+/// before the body is compiled, so that they are not taken for globals
+fn forward_refs(params: &[Ident], body: &[Stmt]) -> Vec<String> {
+    let mut refs = ForwardRefs {
+        declared: params.iter().map(|param| param.0.clone()).collect(),
+        used_early: HashSet::new(),
+        forward: Vec::new(),
+    };
+    refs.visit_stmts(body);
+    refs.forward
+}
+
+struct ForwardRefs {
+    declared: HashSet<String>,
+    used_early: HashSet<String>, // used by nested functions before being declared
+    forward: Vec<String>,
+}
+
+impl ForwardRefs {
+    fn declare(&mut self, name: &str) {
+        if self.declared.insert(name.to_string()) && self.used_early.contains(name) {
+            self.forward.push(name.to_string());
+        }
+    }
+
+    fn visit_stmts(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match &stmt.kind {
+                // a function refers to its own name like the compiler does
+                StmtKind::Let(
+                    ident,
+                    expr @ Expr {
+                        kind: ExprKind::Function(..),
+                        ..
+                    },
+                ) => {
+                    self.declare(&ident.0);
+                    self.visit_expr(expr);
+                }
+                StmtKind::Let(ident, expr) => {
+                    self.visit_expr(expr);
+                    self.declare(&ident.0);
+                }
+                StmtKind::Return(expr) | StmtKind::Expr(expr) => self.visit_expr(expr),
+                StmtKind::Block(stmts) => self.visit_stmts(stmts),
+                StmtKind::While(cond, body) => {
+                    self.visit_expr(cond);
+                    self.visit_stmts(body);
+                }
+                StmtKind::Break | StmtKind::Continue => {}
+            }
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Function(_, body) => {
+                for name in used_names(body) {
+                    if !self.declared.contains(&name) {
+                        self.used_early.insert(name);
+                    }
+                }
+            }
+            ExprKind::Ident(_) | ExprKind::Literal(_) | ExprKind::Macro(..) => {}
+            ExprKind::Prefix(_, right) => self.visit_expr(right),
+            ExprKind::Infix(left, _, right) | ExprKind::Index(left, right) => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            ExprKind::If(cond, cons, alt) => {
+                self.visit_expr(cond);
+                self.visit_stmts(std::slice::from_ref(cons));
+                if let Some(alt) = alt {
+                    self.visit_stmts(std::slice::from_ref(alt));
+                }
+            }
+            ExprKind::Call(func, args) => {
+                self.visit_expr(func);
+                args.iter().for_each(|arg| self.visit_expr(arg));
+            }
+            ExprKind::Array(elements) => elements.iter().for_each(|elem| self.visit_expr(elem)),
+            ExprKind::Hash(pairs) => {
+                for (key, val) in pairs {
+                    self.visit_expr(key);
+                    self.visit_expr(val);
+                }
+            }
+            ExprKind::Assign(_, value) => self.visit_expr(value),
+        }
+    }
+}
+
+/// names of variables read or assigned in the function body, including in functions nested in it
+fn used_names(body: &[Stmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let program = Program {
+        statements: body.to_vec(),
+    };
+    let _ = modify_program(program, &mut |expr: Expr| -> Result<Expr, !> {
+        match &expr.kind {
+            ExprKind::Ident(ident) | ExprKind::Assign(ident, _) => {
+                names.insert(ident.0.clone());
+            }
+            _ => {}
+        }
+        Ok(expr)
+    });
+    names
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::mem;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SymbolScope {
+    Global,
+    Local,
+    /// variable of an enclosing function captured by a closure
+    Free,
+    /// the function currently being defined, referring to itself
+    Function,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub scope: SymbolScope,
+    pub index: usize,
+}
+
+#[derive(Default, Debug)]
+pub struct SymbolTable {
+    outer: Option<Box<SymbolTable>>,
+    store: HashMap<String, Symbol>,
+    pub num_definitions: usize,
+    pub free_symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    pub fn new_enclosed(outer: SymbolTable) -> Self {
+        SymbolTable {
+            outer: Some(Box::new(outer)),
+            ..SymbolTable::default()
+        }
+    }
+
+    /// define binding in this scope. redefinition reuses the slot of the previous one
+    pub fn define(&mut self, name: &str) -> Symbol {
+        let scope = if self.outer.is_some() {
+            SymbolScope::Local
+        } else {
+            SymbolScope::Global
+        };
+
+        if let Some(symbol) = self.store.get(name) {
+            if symbol.scope == scope {
+                return symbol.clone();
+            }
+        }
+
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope,
+            index: self.num_definitions,
+        };
+        self.num_definitions += 1;
+        self.store.insert(name.to_string(), symbol.clone());
+        symbol
+    }
+
+    /// define binding in the outermost scope
+    pub fn define_global(&mut self, name: &str) -> Symbol {
+        match self.outer {
+            Some(ref mut outer) => outer.define_global(name),
+            None => self.define(name),
+        }
+    }
+
+    pub fn define_function_name(&mut self, name: &str) -> Symbol {
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope: SymbolScope::Function,
+            index: 0,
+        };
+        self.store.insert(name.to_string(), symbol.clone());
+        symbol
+    }
+
+    pub fn resolve(&mut self, name: &str) -> Option<Symbol> {
+        if let Some(symbol) = self.store.get(name) {
+            return Some(symbol.clone());
+        }
+
+        let symbol = self.outer.as_mut()?.resolve(name)?;
+        match symbol.scope {
+            SymbolScope::Global => Some(symbol),
+            _ => Some(self.define_free(symbol)),
+        }
+    }
+
+    fn define_free(&mut self, original: Symbol) -> Symbol {
+        let symbol = Symbol {
+            name: original.name.clone(),
+            scope: SymbolScope::Free,
+            index: self.free_symbols.len(),
+        };
+        self.free_symbols.push(original);
+        self.store.insert(symbol.name.clone(), symbol.clone());
+        symbol
+    }
+
+    /// names of global bindings ordered by index
+    pub fn global_names(&self) -> Vec<String> {
+        if let Some(ref outer) = self.outer {
+            return outer.global_names();
+        }
+
+        let mut names = vec![String::new(); self.num_definitions];
+        for symbol in self.store.values() {
+            names[symbol.index] = symbol.name.clone();
+        }
+        names
+    }
+
+    /// replace this table with a new one enclosed by it
+    pub fn enter(&mut self) {
+        let outer = mem::take(self);
+        *self = SymbolTable::new_enclosed(outer);
+    }
+
+    /// restore the enclosing table, returning the one left
+    pub fn leave(&mut self) -> SymbolTable {
+        let outer = self.outer.take().expect("leave global symbol table");
+        mem::replace(self, *outer)
+    }
+}
@@ -0,0 +1,214 @@
+use super::*;
+use crate::code::disassemble;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+fn test_compile(input: &str) -> Bytecode {
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+    assert!(p.errors.is_empty(), "{:?}", p.errors);
+
+    Compiler::new().compile(&program).unwrap()
+}
+
+fn function_instructions(obj: &Object) -> String {
+    match obj {
+        Object::CompiledFunction(func) => disassemble(&func.instructions),
+        obj => panic!("expected compiled function. got {}", obj),
+    }
+}
+
+#[test]
+fn test_compile_expressions() {
+    let test_cases = vec![
+        (
+            "1 + 2",
+            "0000 Constant 0\n0003 Constant 1\n0006 Add\n0007 Pop\n",
+        ),
+        ("-1; !true", "0000 Constant 0\n0003 Minus\n0004 Pop\n0005 True\n0006 Bang\n0007 Pop\n"),
+        (
+            "[1, 2][0]",
+            "0000 Constant 0\n0003 Constant 1\n0006 Array 2\n0009 Constant 2\n0012 Index\n0013 Pop\n",
+        ),
+        (
+            "{1: 2}",
+            "0000 Constant 0\n0003 Constant 1\n0006 Hash 2\n0009 Pop\n",
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(disassemble(&test_compile(input).instructions), expect);
+    }
+}
+
+#[test]
+fn test_compile_conditionals() {
+    let test_cases = vec![
+        (
+            "if (true) { 10 }; 3333;",
+            "0000 True\n0001 JumpNotTruthy 10\n0004 Constant 0\n0007 Jump 11\n0010 Null\n0011 Pop\n0012 Constant 1\n0015 Pop\n",
+        ),
+        (
+            "if (true) { let a = 1; } else { 20 }",
+            "0000 True\n0001 JumpNotTruthy 14\n0004 Constant 0\n0007 SetGlobal 0\n0010 Null\n0011 Jump 17\n0014 Constant 1\n0017 Pop\n",
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(disassemble(&test_compile(input).instructions), expect);
+    }
+}
+
+#[test]
+fn test_compile_while() {
+    let bytecode = test_compile("while (true) { if (false) { break; } continue; }");
+
+    assert_eq!(
+        disassemble(&bytecode.instructions),
+        "0000 True\n0001 JumpNotTruthy 23\n0004 False\n0005 JumpNotTruthy 15\n0008 Jump 23\n0011 Null\n0012 Jump 16\n0015 Null\n0016 Pop\n0017 Jump 0\n0020 Jump 0\n0023 Null\n0024 Pop\n"
+    );
+}
+
+#[test]
+fn test_compile_logical_operators() {
+    let test_cases = vec![
+        (
+            "true && false",
+            "0000 True\n0001 JumpNotTruthy 10\n0004 False\n0005 Bang\n0006 Bang\n0007 Jump 11\n0010 False\n0011 Pop\n",
+        ),
+        (
+            "true || false",
+            "0000 True\n0001 JumpNotTruthy 8\n0004 True\n0005 Jump 11\n0008 False\n0009 Bang\n0010 Bang\n0011 Pop\n",
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(disassemble(&test_compile(input).instructions), expect);
+    }
+}
+
+#[test]
+fn test_compile_let_stmts() {
+    let bytecode = test_compile("let one = 1; let two = one; two");
+
+    assert_eq!(
+        disassemble(&bytecode.instructions),
+        "0000 Constant 0\n0003 SetGlobal 0\n0006 GetGlobal 0\n0009 SetGlobal 1\n0012 GetGlobal 1\n0015 Pop\n"
+    );
+    assert_eq!(bytecode.global_names, vec!["one", "two"]);
+}
+
+#[test]
+fn test_compile_functions() {
+    let bytecode = test_compile("let f = fn(a) { let b = a; fn() { a + b } }; f");
+
+    assert_eq!(
+        disassemble(&bytecode.instructions),
+        "0000 Closure 1 0\n0004 SetGlobal 0\n0007 GetGlobal 0\n0010 Pop\n"
+    );
+    assert_eq!(
+        function_instructions(&bytecode.constants[0]),
+        "0000 GetFree 0\n0002 GetFree 1\n0004 Add\n0005 ReturnValue\n"
+    );
+    assert_eq!(
+        function_instructions(&bytecode.constants[1]),
+        "0000 GetLocal 0\n0002 SetLocal 1\n0004 CaptureLocal 0\n0006 CaptureLocal 1\n0008 Closure 0 2\n0012 ReturnValue\n"
+    );
+}
+
+#[test]
+fn test_compile_recursive_function() {
+    let bytecode = test_compile("let f = fn(x) { f(x) };");
+
+    assert_eq!(
+        function_instructions(&bytecode.constants[0]),
+        "0000 CurrentClosure\n0001 GetLocal 0\n0003 Call 1\n0005 ReturnValue\n"
+    );
+}
+
+#[test]
+fn test_compile_assignment() {
+    let bytecode = test_compile("let x = 1; x = 2; let f = fn(n) { fn() { n += 1 } };");
+
+    assert_eq!(
+        disassemble(&bytecode.instructions),
+        "0000 Constant 0\n0003 SetGlobal 0\n0006 Constant 1\n0009 AssignGlobal 0\n0012 GetGlobal 0\n0015 Pop\n0016 Closure 4 0\n0020 SetGlobal 1\n0023 Null\n0024 Pop\n"
+    );
+    // the parameter assigned by the inner function is kept in a cell
+    assert_eq!(
+        function_instructions(&bytecode.constants[3]),
+        "0000 GetFree 0\n0002 Constant 2\n0005 Add\n0006 SetFree 0\n0008 GetFree 0\n0010 ReturnValue\n"
+    );
+    assert_eq!(
+        function_instructions(&bytecode.constants[4]),
+        "0000 MakeCell 0\n0002 CaptureLocal 0\n0004 Closure 3 1\n0008 ReturnValue\n"
+    );
+}
+
+#[test]
+fn test_compile_forward_reference() {
+    let bytecode = test_compile("fn() { let g = fn() { y }; let y = 2; g() }");
+
+    // `y` is a cell captured by `g` before it is bound
+    assert_eq!(
+        function_instructions(&bytecode.constants[2]),
+        "0000 MakeCell 0\n0002 CaptureLocal 0\n0004 Closure 0 1\n0008 SetLocal 1\n0010 Constant 1\n0013 SetLocal 0\n0015 GetLocal 1\n0017 Call 0\n0019 ReturnValue\n"
+    );
+    assert!(bytecode.global_names.is_empty());
+}
+
+#[test]
+fn test_compile_unresolved_ident() {
+    let bytecode = test_compile("len(x)");
+
+    assert_eq!(
+        disassemble(&bytecode.instructions),
+        "0000 GetGlobal 0\n0003 GetGlobal 1\n0006 Call 1\n0008 Pop\n"
+    );
+    assert_eq!(bytecode.global_names, vec!["len", "x"]);
+}
+
+#[test]
+fn test_compile_large_body() {
+    let body = "x; ".repeat(20000);
+
+    for input in [
+        format!("let x = 1; if (true) {{ {} }}", body),
+        format!("let x = 1; while (true) {{ {} }}", body),
+    ] {
+        let mut l = Lexer::new(&input);
+        let mut p = Parser::new(&mut l);
+        let program = p.parse_program();
+
+        let err = Compiler::new().compile(&program).unwrap_err();
+        assert!(
+            err.msg.starts_with("too many operands for JumpNotTruthy"),
+            "{}",
+            err
+        );
+    }
+}
+
+#[test]
+fn test_symbol_table() {
+    let mut global = SymbolTable::new();
+    assert_eq!(global.define("a").index, 0);
+    assert_eq!(global.define("b").index, 1);
+    assert_eq!(global.define("a").index, 0);
+
+    global.enter();
+    let local = global.define("c");
+    assert_eq!(local.scope, SymbolScope::Local);
+    assert_eq!(global.resolve("a").unwrap().scope, SymbolScope::Global);
+
+    global.enter();
+    let free = global.resolve("c").unwrap();
+    assert_eq!(free.scope, SymbolScope::Free);
+    assert_eq!(global.free_symbols, vec![local]);
+    assert_eq!(global.resolve("d"), None);
+
+    global.leave();
+    global.leave();
+    assert_eq!(global.global_names(), vec!["a", "b"]);
+}
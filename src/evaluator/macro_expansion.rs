@@ -0,0 +1,62 @@
+use super::apply_function;
+use crate::ast::{modify_program, Expr, ExprKind, Program, Stmt, StmtKind};
+use crate::object::{Env, EvalError, EvalResult, Func, Object};
+use std::mem;
+
+/// remove top-level `let name = macro(..) { .. }` statements, binding the macros in `env`
+pub fn define_macros(program: &mut Program, env: &Env) {
+    for stmt in mem::take(&mut program.statements) {
+        match stmt.kind {
+            StmtKind::Let(
+                ident,
+                Expr {
+                    kind: ExprKind::Macro(args, body),
+                    ..
+                },
+            ) => {
+                let mac = Object::Macro(Func {
+                    name: Some(ident.0.clone()),
+                    args,
+                    body,
+                    env: env.clone(),
+                });
+                env.insert(ident.0, mac);
+            }
+            kind => program.statements.push(Stmt::new(kind, stmt.span)),
+        }
+    }
+}
+
+/// replace calls of macros bound in `env` with the AST nodes they return
+pub fn expand_macros(program: Program, env: &Env) -> EvalResult<Program> {
+    modify_program(program, &mut |expr: Expr| {
+        let mac = match &expr.kind {
+            ExprKind::Call(func, _) => match &func.kind {
+                ExprKind::Ident(ident) => match env.get(&ident.0) {
+                    Some(Object::Macro(mac)) => mac,
+                    _ => return Ok(expr),
+                },
+                _ => return Ok(expr),
+            },
+            _ => return Ok(expr),
+        };
+
+        let span = expr.span;
+        match expr.kind {
+            ExprKind::Call(_, args) => expand_macro(mac, args).map_err(|err| err.or_span(span)),
+            _ => unreachable!(),
+        }
+    })
+}
+
+fn expand_macro(mac: Func, args: Vec<Expr>) -> EvalResult<Expr> {
+    let args = args.into_iter().map(Object::Quote).collect();
+
+    match apply_function(mac, args)? {
+        Object::Quote(expr) => Ok(expr),
+        obj => Err(EvalError::new(format!(
+            "macro must return quoted AST node, got {}",
+            obj.get_type()
+        ))),
+    }
+}
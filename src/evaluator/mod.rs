@@ -1,14 +1,59 @@
-use crate::ast::{Expr, Ident, Infix, Literal, Prefix, Program, Stmt};
-use crate::object::{Env, EvalError, EvalResult, Func, Object};
+use crate::ast::{Expr, ExprKind, Ident, Infix, Literal, Prefix, Program, Stmt, StmtKind};
+use crate::builtins;
+use crate::object::{Env, EvalError, EvalResult, Func, HashKey, Object};
+use crate::token::Span;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::collections::BTreeMap;
+
+mod macro_expansion;
+pub mod quote;
+
+pub use self::macro_expansion::{define_macros, expand_macros};
 
 #[cfg(test)]
 mod test;
 
-pub fn eval(p: Program, env: &mut Env) -> EvalResult<Object> {
+/// why evaluation stopped without a value. `break` and `continue` are not
+/// errors, but unwind the same way to the innermost loop
+enum Interrupt {
+    Error(EvalError),
+    Break(Span),
+    Continue(Span),
+}
+
+type Flow<T> = Result<T, Interrupt>;
+
+impl From<EvalError> for Interrupt {
+    fn from(err: EvalError) -> Self {
+        Interrupt::Error(err)
+    }
+}
+
+impl Interrupt {
+    /// error for the interrupt if no loop stopped it before a function
+    /// boundary. the parser accepts `break` and `continue` only in loops
+    fn into_error(self) -> EvalError {
+        match self {
+            Interrupt::Error(err) => err,
+            Interrupt::Break(span) => outside_loop_error("break").or_span(span),
+            Interrupt::Continue(span) => outside_loop_error("continue").or_span(span),
+        }
+    }
+
+    fn or_span(self, span: Span) -> Self {
+        match self {
+            Interrupt::Error(err) => Interrupt::Error(err.or_span(span)),
+            interrupt => interrupt,
+        }
+    }
+}
+
+pub fn eval(p: Program, env: &Env) -> EvalResult<Object> {
     let mut result = Object::Null;
 
     for stmt in p.statements {
-        result = eval_stmt(env, stmt)?;
+        result = eval_stmt(env, stmt).map_err(Interrupt::into_error)?;
 
         if let Object::Return(box val) = result {
             return Ok(val);
@@ -18,49 +63,120 @@ pub fn eval(p: Program, env: &mut Env) -> EvalResult<Object> {
     Ok(result)
 }
 
-fn eval_stmt(env: &mut Env, stmt: Stmt) -> EvalResult<Object> {
-    match stmt {
-        Stmt::Expr(expr) => eval_expr(env, expr),
-        Stmt::Block(stmts) => eval_block_stmt(env, stmts),
-        Stmt::Return(expr) => {
+fn eval_stmt(env: &Env, stmt: Stmt) -> Flow<Object> {
+    match stmt.kind {
+        StmtKind::Expr(expr) => eval_expr(env, expr),
+        StmtKind::Block(stmts) => eval_block_stmt(env, stmts),
+        StmtKind::Return(expr) => {
             let val = eval_expr(env, expr);
             val.map(|v| Object::Return(Box::new(v)))
         }
-        Stmt::Let(ident, expr) => {
-            let val = eval_expr(env, expr)?;
+        StmtKind::Let(ident, expr) => {
+            let val = match expr.kind {
+                // named after the binding, like the compiler does
+                ExprKind::Function(args, body) => Object::Func(Func {
+                    name: Some(ident.0.clone()),
+                    args,
+                    body,
+                    env: env.clone(),
+                }),
+                kind => eval_expr(env, Expr::new(kind, expr.span))?,
+            };
             env.insert(ident.0, val);
             Ok(Object::Null)
         }
+        StmtKind::While(cond, body) => eval_while_stmt(env, cond, body),
+        StmtKind::Break => Err(Interrupt::Break(stmt.span)),
+        StmtKind::Continue => Err(Interrupt::Continue(stmt.span)),
     }
 }
 
-fn eval_expr(env: &mut Env, expr: Expr) -> EvalResult<Object> {
-    match expr {
-        Expr::Literal(literal) => Ok(eval_literal(literal)),
-        Expr::Prefix(prefix, right) => eval_prefix_expr(prefix, eval_expr(env, *right)?),
-        Expr::Infix(left, infix, right) => {
-            eval_infix_expr(infix, eval_expr(env, *left)?, eval_expr(env, *right)?)
+fn eval_while_stmt(env: &Env, cond: Expr, body: Vec<Stmt>) -> Flow<Object> {
+    while is_truthy(&eval_expr(env, cond.clone())?) {
+        match eval_block_stmt(env, body.clone()) {
+            Ok(Object::Return(val)) => return Ok(Object::Return(val)),
+            Ok(_) | Err(Interrupt::Continue(_)) => {}
+            Err(Interrupt::Break(_)) => break,
+            Err(err) => return Err(err),
         }
-        Expr::If(cond, cons, alt) => eval_if_expr(env, *cond, *cons, alt),
-        Expr::Ident(ident) => eval_ident(env, ident),
-        Expr::Function(idents, stmts) => Ok(Object::Func(Func {
+    }
+    Ok(Object::Null)
+}
+
+fn eval_expr(env: &Env, expr: Expr) -> Flow<Object> {
+    let span = expr.span;
+    eval_expr_kind(env, expr.kind).map_err(|err| err.or_span(span))
+}
+
+fn eval_expr_kind(env: &Env, kind: ExprKind) -> Flow<Object> {
+    match kind {
+        ExprKind::Literal(literal) => Ok(eval_literal(literal)),
+        ExprKind::Prefix(prefix, right) => Ok(eval_prefix_expr(prefix, eval_expr(env, *right)?)?),
+        ExprKind::Infix(left, infix @ Infix::And, right)
+        | ExprKind::Infix(left, infix @ Infix::Or, right) => {
+            eval_logical_expr(env, *left, infix, *right)
+        }
+        ExprKind::Infix(left, infix, right) => {
+            let (left, right) = (eval_expr(env, *left)?, eval_expr(env, *right)?);
+            Ok(eval_infix_expr(infix, left, right)?)
+        }
+        ExprKind::If(cond, cons, alt) => eval_if_expr(env, *cond, *cons, alt),
+        ExprKind::Ident(ident) => Ok(eval_ident(env, ident)?),
+        ExprKind::Function(idents, stmts) => Ok(Object::Func(Func {
+            name: None,
+            args: idents,
+            body: stmts,
+            env: env.clone(), // shares the defining scope, not a copy
+        })),
+        ExprKind::Macro(idents, stmts) => Ok(Object::Macro(Func {
+            name: None,
             args: idents,
             body: stmts,
             env: env.clone(),
         })),
-        Expr::Call(box func_expr, args) => {
-            let func_obj = eval_expr(env, func_expr)?;
-            let args = args
+        ExprKind::Call(func_expr, args) if quote::is_quote(&func_expr) => eval_quote(env, args),
+        ExprKind::Call(box func_expr, args) => {
+            let (func_obj, args) = eval_call_operands(env, func_expr, args)?;
+            Ok(match func_obj {
+                Object::Builtin(builtin) => (builtin.func)(args),
+                obj => apply_function(cast_obj_to_func(obj)?, args),
+            }?)
+        }
+        ExprKind::Array(elements) => Ok(Object::Array(
+            elements
                 .into_iter()
-                .map(|arg| eval_expr(env, arg))
-                .collect::<EvalResult<Vec<Object>>>()?;
-            let func = cast_obj_to_func(func_obj)?;
-            apply_function(func, args)
+                .map(|elem| eval_expr(env, elem))
+                .collect::<Flow<Vec<Object>>>()?,
+        )),
+        ExprKind::Hash(pairs) => eval_hash_literal(env, pairs),
+        ExprKind::Assign(ident, box value) => {
+            let val = eval_expr(env, value)?;
+            if env.assign(&ident.0, val.clone()) {
+                Ok(val)
+            } else {
+                Err(undeclared_assignment_error(&ident.0).into())
+            }
+        }
+        ExprKind::Index(box left, box index) => {
+            let (left, index) = (eval_expr(env, left)?, eval_expr(env, index)?);
+            Ok(eval_index_expr(left, index)?)
         }
     }
 }
 
-fn eval_block_stmt(env: &mut Env, block: Vec<Stmt>) -> EvalResult<Object> {
+fn eval_quote(env: &Env, mut args: Vec<Expr>) -> Flow<Object> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        ))
+        .into());
+    }
+
+    quote::quote(args.pop().unwrap(), |arg| eval_expr(env, arg))
+}
+
+fn eval_block_stmt(env: &Env, block: Vec<Stmt>) -> Flow<Object> {
     let mut result = Object::Null;
 
     for stmt in block {
@@ -78,14 +194,17 @@ fn eval_literal(literal: Literal) -> Object {
     match literal {
         Literal::Bool(v) => Object::Bool(v),
         Literal::Int(v) => Object::Int(v),
+        Literal::BigInt(v) => Object::BigInt(v),
+        Literal::Float(v) => Object::Float(v),
+        Literal::Str(v) => Object::Str(v),
     }
 }
 
-fn eval_prefix_expr(operator: Prefix, right: Object) -> EvalResult<Object> {
+pub(crate) fn eval_prefix_expr(operator: Prefix, right: Object) -> EvalResult<Object> {
     match operator {
         Prefix::Not => Ok(eval_bang_operator_expr(right)),
         Prefix::Minus => eval_minus_operator_expr(right),
-        _ => Err(EvalError(format!(
+        _ => Err(EvalError::new(format!(
             "unknown operator: {}{}",
             operator,
             right.get_type()
@@ -93,42 +212,169 @@ fn eval_prefix_expr(operator: Prefix, right: Object) -> EvalResult<Object> {
     }
 }
 
-fn eval_infix_expr(operator: Infix, left: Object, right: Object) -> EvalResult<Object> {
-    if let Object::Int(left_val) = left {
-        if let Object::Int(right_val) = right {
-            Ok(eval_int_infix_expr(operator, left_val, right_val))
-        } else {
-            Err(EvalError(format!(
-                "type mismatch: {} {} {}",
-                left.get_type(),
-                operator,
-                right.get_type()
-            )))
+pub(crate) fn eval_infix_expr(operator: Infix, left: Object, right: Object) -> EvalResult<Object> {
+    match (left, right) {
+        (Object::Int(left_val), Object::Int(right_val)) => {
+            eval_int_infix_expr(operator, left_val, right_val)
         }
-    } else {
-        match operator {
+        (Object::Float(left_val), Object::Float(right_val)) => {
+            Ok(eval_float_infix_expr(operator, left_val, right_val))
+        }
+        // int is promoted to float when mixed
+        (Object::Float(left_val), right) if is_integer(&right) => Ok(eval_float_infix_expr(
+            operator,
+            left_val,
+            int_to_float(right),
+        )),
+        (left, Object::Float(right_val)) if is_integer(&left) => Ok(eval_float_infix_expr(
+            operator,
+            int_to_float(left),
+            right_val,
+        )),
+        (left, right) if is_integer(&left) && is_integer(&right) => {
+            eval_big_int_infix_expr(operator, to_big_int(left), to_big_int(right))
+        }
+        (Object::Str(left_val), Object::Str(right_val)) => {
+            eval_str_infix_expr(operator, left_val, right_val)
+        }
+        (left @ Object::Int(_), right)
+        | (left @ Object::BigInt(_), right)
+        | (left @ Object::Float(_), right)
+        | (left @ Object::Str(_), right) => Err(EvalError::new(format!(
+            "type mismatch: {} {} {}",
+            left.get_type(),
+            operator,
+            right.get_type()
+        ))),
+        (left, right) => match operator {
             Infix::Equal => Ok(Object::Bool(left == right)),
             Infix::NotEqual => Ok(Object::Bool(left != right)),
-            _ => Err(EvalError(format!(
+            _ => Err(EvalError::new(format!(
                 "unknown operator: {} {} {}",
                 left.get_type(),
                 operator,
                 right.get_type()
             ))),
+        },
+    }
+}
+
+/// `/` truncates toward zero and `%` takes the sign of the dividend, as in Rust
+fn eval_int_infix_expr(operator: Infix, left: i64, right: i64) -> EvalResult<Object> {
+    let checked = match operator {
+        Infix::Plus => left.checked_add(right),
+        Infix::Minus => left.checked_sub(right),
+        Infix::Multiply => left.checked_mul(right),
+        Infix::Divide | Infix::Modulo if right == 0 => {
+            return Err(EvalError::new("division by zero".to_string()))
+        }
+        Infix::Divide => left.checked_div(right),
+        Infix::Modulo => left.checked_rem(right),
+        Infix::Power if right < 0 => return Ok(Object::Float((left as f64).powf(right as f64))),
+        Infix::Power => right.to_u32().and_then(|exp| left.checked_pow(exp)),
+        Infix::LessThan => return Ok(Object::Bool(left < right)),
+        Infix::GreaterThan => return Ok(Object::Bool(left > right)),
+        Infix::LessOrEqual => return Ok(Object::Bool(left <= right)),
+        Infix::GreaterOrEqual => return Ok(Object::Bool(left >= right)),
+        Infix::Equal => return Ok(Object::Bool(left == right)),
+        Infix::NotEqual => return Ok(Object::Bool(left != right)),
+        Infix::And | Infix::Or => unreachable!("logical operators short-circuit"),
+    };
+
+    match checked {
+        Some(val) => Ok(Object::Int(val)),
+        None => eval_big_int_infix_expr(operator, BigInt::from(left), BigInt::from(right)),
+    }
+}
+
+fn eval_big_int_infix_expr(operator: Infix, left: BigInt, right: BigInt) -> EvalResult<Object> {
+    let val = match operator {
+        Infix::Plus => left + right,
+        Infix::Minus => left - right,
+        Infix::Multiply => left * right,
+        Infix::Divide | Infix::Modulo if right.is_zero() => {
+            return Err(EvalError::new("division by zero".to_string()))
         }
+        Infix::Divide => left / right,
+        Infix::Modulo => left % right,
+        Infix::Power => match right.to_u32() {
+            Some(exp) => left.pow(exp),
+            None if right.is_negative() => {
+                let (left, right) = (Object::BigInt(left), Object::BigInt(right));
+                return Ok(Object::Float(int_to_float(left).powf(int_to_float(right))));
+            }
+            None => return Err(EvalError::new(format!("exponent too large: {}", right))),
+        },
+        Infix::LessThan => return Ok(Object::Bool(left < right)),
+        Infix::GreaterThan => return Ok(Object::Bool(left > right)),
+        Infix::LessOrEqual => return Ok(Object::Bool(left <= right)),
+        Infix::GreaterOrEqual => return Ok(Object::Bool(left >= right)),
+        Infix::Equal => return Ok(Object::Bool(left == right)),
+        Infix::NotEqual => return Ok(Object::Bool(left != right)),
+        Infix::And | Infix::Or => unreachable!("logical operators short-circuit"),
+    };
+
+    Ok(Object::from_big_int(val))
+}
+
+/// evaluates right operand only when left one does not decide the result
+fn eval_logical_expr(env: &Env, left: Expr, operator: Infix, right: Expr) -> Flow<Object> {
+    let left = is_truthy(&eval_expr(env, left)?);
+    if left == (operator == Infix::Or) {
+        return Ok(Object::Bool(left));
+    }
+
+    Ok(Object::Bool(is_truthy(&eval_expr(env, right)?)))
+}
+
+fn is_integer(obj: &Object) -> bool {
+    matches!(obj, Object::Int(_) | Object::BigInt(_))
+}
+
+fn to_big_int(obj: Object) -> BigInt {
+    match obj {
+        Object::Int(val) => BigInt::from(val),
+        Object::BigInt(val) => val,
+        obj => unreachable!("not an integer: {}", obj),
     }
 }
 
-fn eval_int_infix_expr(operator: Infix, left: i64, right: i64) -> Object {
+fn int_to_float(obj: Object) -> f64 {
+    match obj {
+        Object::Int(val) => val as f64,
+        Object::BigInt(val) => val.to_f64().unwrap_or(f64::NAN),
+        obj => unreachable!("not an integer: {}", obj),
+    }
+}
+
+/// follows IEEE 754, so dividing by zero gives an infinity or `NaN` instead of an error
+fn eval_float_infix_expr(operator: Infix, left: f64, right: f64) -> Object {
     match operator {
-        Infix::Plus => Object::Int(left + right),
-        Infix::Minus => Object::Int(left - right),
-        Infix::Multiply => Object::Int(left * right),
-        Infix::Divide => Object::Int(left / right),
+        Infix::Plus => Object::Float(left + right),
+        Infix::Minus => Object::Float(left - right),
+        Infix::Multiply => Object::Float(left * right),
+        Infix::Divide => Object::Float(left / right),
+        Infix::Modulo => Object::Float(left % right),
+        Infix::Power => Object::Float(left.powf(right)),
         Infix::LessThan => Object::Bool(left < right),
         Infix::GreaterThan => Object::Bool(left > right),
+        Infix::LessOrEqual => Object::Bool(left <= right),
+        Infix::GreaterOrEqual => Object::Bool(left >= right),
         Infix::Equal => Object::Bool(left == right),
         Infix::NotEqual => Object::Bool(left != right),
+        Infix::And | Infix::Or => unreachable!("logical operators short-circuit"),
+    }
+}
+
+fn eval_str_infix_expr(operator: Infix, left: String, right: String) -> EvalResult<Object> {
+    match operator {
+        Infix::Plus => Ok(Object::Str(left + &right)),
+        Infix::Equal => Ok(Object::Bool(left == right)),
+        Infix::NotEqual => Ok(Object::Bool(left != right)),
+        _ => Err(EvalError::new(format!(
+            "unknown operator: STRING {} STRING",
+            operator
+        ))),
     }
 }
 
@@ -141,25 +387,69 @@ fn eval_bang_operator_expr(right: Object) -> Object {
 }
 
 fn eval_minus_operator_expr(right: Object) -> EvalResult<Object> {
-    if let Object::Int(val) = right {
-        Ok(Object::Int(-val))
-    } else {
-        Err(EvalError(format!(
+    match right {
+        Object::Int(val) => Ok(match val.checked_neg() {
+            Some(val) => Object::Int(val),
+            None => Object::BigInt(-BigInt::from(val)),
+        }),
+        Object::BigInt(val) => Ok(Object::from_big_int(-val)),
+        Object::Float(val) => Ok(Object::Float(-val)),
+        _ => Err(EvalError::new(format!(
             "unknown operator: -{}",
             right.get_type()
-        )))
+        ))),
+    }
+}
+
+pub(crate) fn eval_index_expr(left: Object, index: Object) -> EvalResult<Object> {
+    match (left, index) {
+        (Object::Array(elements), Object::Int(idx)) => Ok(eval_array_index_expr(elements, idx)),
+        // beyond any array length
+        (Object::Array(_), Object::BigInt(_)) => Ok(Object::Null),
+        (Object::Array(_), index) => Err(EvalError::new(format!(
+            "array index must be INT, got {}",
+            index.get_type()
+        ))),
+        (Object::Hash(mut pairs), key) => {
+            let key = to_hash_key(key)?;
+            Ok(pairs.remove(&key).unwrap_or(Object::Null))
+        }
+        (left, _) => Err(EvalError::new(format!(
+            "index operator not supported: {}",
+            left.get_type()
+        ))),
+    }
+}
+
+fn eval_array_index_expr(mut elements: Vec<Object>, idx: i64) -> Object {
+    if idx < 0 || idx as usize >= elements.len() {
+        Object::Null
+    } else {
+        elements.swap_remove(idx as usize)
+    }
+}
+
+fn eval_hash_literal(env: &Env, pairs: Vec<(Expr, Expr)>) -> Flow<Object> {
+    let mut hash = BTreeMap::new();
+
+    for (key_expr, val_expr) in pairs {
+        let key = to_hash_key(eval_expr(env, key_expr)?)?;
+        let val = eval_expr(env, val_expr)?;
+        hash.insert(key, val);
     }
+
+    Ok(Object::Hash(hash))
 }
 
-fn eval_if_expr(
-    env: &mut Env,
-    cond: Expr,
-    cons: Stmt,
-    alt: Option<Box<Stmt>>,
-) -> EvalResult<Object> {
+pub(crate) fn to_hash_key(obj: Object) -> EvalResult<HashKey> {
+    obj.to_hash_key()
+        .ok_or_else(|| EvalError::new(format!("unusable as hash key: {}", obj.get_type())))
+}
+
+fn eval_if_expr(env: &Env, cond: Expr, cons: Stmt, alt: Option<Box<Stmt>>) -> Flow<Object> {
     let cond_obj = eval_expr(env, cond)?;
 
-    if is_truthy(cond_obj) {
+    if is_truthy(&cond_obj) {
         return eval_stmt(env, cons);
     };
 
@@ -170,7 +460,7 @@ fn eval_if_expr(
     Ok(Object::Null)
 }
 
-fn is_truthy(obj: Object) -> bool {
+pub(crate) fn is_truthy(obj: &Object) -> bool {
     match obj {
         Object::Null => false,
         Object::Bool(true) => true,
@@ -180,27 +470,146 @@ fn is_truthy(obj: Object) -> bool {
 }
 
 fn eval_ident(env: &Env, ident: Ident) -> EvalResult<Object> {
-    let val = env.get(ident.0.clone());
-    if let Some(obj) = val {
-        Ok(obj.clone())
+    if let Some(obj) = env.get(&ident.0) {
+        Ok(obj)
+    } else if let Some(builtin) = builtins::lookup(&ident.0) {
+        Ok(builtin)
     } else {
-        Err(EvalError(format!(r#"identifier not found: {}"#, ident)))
+        Err(EvalError::new(format!(
+            r#"identifier not found: {}"#,
+            ident
+        )))
+    }
+}
+
+fn eval_call_operands(env: &Env, func_expr: Expr, args: Vec<Expr>) -> Flow<(Object, Vec<Object>)> {
+    let func_obj = eval_expr(env, func_expr)?;
+    let args = args
+        .into_iter()
+        .map(|arg| eval_expr(env, arg))
+        .collect::<Flow<Vec<Object>>>()?;
+    Ok((func_obj, args))
+}
+
+/// result of evaluating the body of a function, which may end with a call still to be made
+enum Tail {
+    Value(Object),
+    Call(Func, Vec<Object>),
+}
+
+/// calls in tail position are made in this loop rather than recursively,
+/// so tail-recursive functions run in constant native stack space
+fn apply_function(mut func: Func, mut args: Vec<Object>) -> EvalResult<Object> {
+    let _call = func
+        .env
+        .enter_call()
+        .ok_or_else(|| call_depth_error(func.env.max_call_depth(), func.name.as_deref()))?;
+    // the arguments of tail calls are checked where they are made
+    check_arity(func.args.len(), args.len())?;
+    loop {
+        let wrapped_env = wrap_function_env(&func, args);
+        match eval_tail_block(&wrapped_env, func.body, true).map_err(Interrupt::into_error)? {
+            Tail::Value(evaluated) => return Ok(unwrap_return_value(evaluated)),
+            Tail::Call(next_func, next_args) => {
+                func = next_func;
+                args = next_args;
+            }
+        }
+    }
+}
+
+/// like `eval_block_stmt`, but `return` and, when `tail` is set, the last
+/// statement leave calls of functions to the caller
+fn eval_tail_block(env: &Env, block: Vec<Stmt>, tail: bool) -> Flow<Tail> {
+    let mut result = Tail::Value(Object::Null);
+    let last = block.len().saturating_sub(1);
+
+    for (idx, stmt) in block.into_iter().enumerate() {
+        result = eval_tail_stmt(env, stmt, tail && idx == last)?;
+
+        if let Tail::Call(..) | Tail::Value(Object::Return(_)) = result {
+            return Ok(result);
+        }
+    }
+
+    Ok(result)
+}
+
+fn eval_tail_stmt(env: &Env, stmt: Stmt, tail: bool) -> Flow<Tail> {
+    match stmt.kind {
+        StmtKind::Return(expr) => match eval_tail_expr(env, expr, true)? {
+            Tail::Value(val) => Ok(Tail::Value(Object::Return(Box::new(val)))),
+            call => Ok(call),
+        },
+        StmtKind::Expr(expr) => eval_tail_expr(env, expr, tail),
+        StmtKind::Block(stmts) => eval_tail_block(env, stmts, tail),
+        kind => eval_stmt(env, Stmt::new(kind, stmt.span)).map(Tail::Value),
+    }
+}
+
+fn eval_tail_expr(env: &Env, expr: Expr, tail: bool) -> Flow<Tail> {
+    let span = expr.span;
+    match expr.kind {
+        ExprKind::If(cond, cons, alt) => {
+            if is_truthy(&eval_expr(env, *cond)?) {
+                eval_tail_stmt(env, *cons, tail)
+            } else if let Some(alt) = alt {
+                eval_tail_stmt(env, *alt, tail)
+            } else {
+                Ok(Tail::Value(Object::Null))
+            }
+        }
+        ExprKind::Call(func_expr, args) if tail && !quote::is_quote(&func_expr) => {
+            let (func_obj, args) = eval_call_operands(env, *func_expr, args)?;
+            let tail = match func_obj {
+                Object::Builtin(builtin) => (builtin.func)(args).map(Tail::Value),
+                obj => cast_obj_to_func(obj).and_then(|func| {
+                    check_arity(func.args.len(), args.len())?;
+                    Ok(Tail::Call(func, args))
+                }),
+            };
+            Ok(tail.map_err(|err| err.or_span(span))?)
+        }
+        kind => eval_expr(env, Expr::new(kind, span)).map(Tail::Value),
     }
 }
 
-fn apply_function(func: Func, args: Vec<Object>) -> EvalResult<Object> {
-    let mut wrapped_env = wrap_function_env(&func, args);
-    let evaluated = eval_block_stmt(&mut wrapped_env, func.body)?;
+/// error for `break` or `continue` not in a loop of the same function. the
+/// parser rejects them, so only ASTs not built by it can have one
+pub(crate) fn outside_loop_error(keyword: &str) -> EvalError {
+    EvalError::new(format!("'{}' outside loop", keyword))
+}
+
+pub(crate) fn undeclared_assignment_error(name: &str) -> EvalError {
+    EvalError::new(format!("assignment to undeclared variable: {}", name))
+}
+
+/// fails unless a function is called with as many arguments as it has parameters
+pub(crate) fn check_arity(num_params: usize, num_args: usize) -> EvalResult<()> {
+    if num_args == num_params {
+        Ok(())
+    } else {
+        Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want={}",
+            num_args, num_params
+        )))
+    }
+}
 
-    Ok(unwrap_return_value(evaluated))
+pub(crate) fn call_depth_error(max_call_depth: usize, name: Option<&str>) -> EvalError {
+    EvalError::new(format!(
+        "maximum call depth ({}) exceeded in {}",
+        max_call_depth,
+        name.unwrap_or("anonymous function")
+    ))
 }
 
 fn wrap_function_env(func: &Func, args: Vec<Object>) -> Env {
-    let mut env = Env::wrap(func.env.clone());
+    let env = Env::wrap(&func.env);
 
     func.args
         .iter()
-        .zip(args.into_iter())
+        .zip(args)
         .for_each(|(arg_name, arg_value)| {
             env.insert(arg_name.0.clone(), arg_value);
         });
@@ -212,13 +621,13 @@ fn cast_obj_to_func(obj: Object) -> EvalResult<Func> {
     if let Object::Func(func) = obj {
         Ok(func)
     } else {
-        Err(EvalError(format!("'{}' is not function object", obj)))
+        Err(EvalError::new(format!("'{}' is not function object", obj)))
     }
 }
 
 fn unwrap_return_value(obj: Object) -> Object {
     if let Object::Return(box value) = obj {
-        return value;
+        value
     } else {
         obj
     }
@@ -0,0 +1,73 @@
+use crate::ast::{modify_expr, Expr, ExprKind, Ident, Literal};
+use crate::object::{EvalError, EvalResult, Object};
+use crate::token::Span;
+
+/// whether `func` is the `quote` in `quote(expr)`. quote is syntax, not a binding
+pub fn is_quote(func: &Expr) -> bool {
+    matches!(&func.kind, ExprKind::Ident(Ident(name)) if name == "quote")
+}
+
+fn is_unquote_call(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Call(func, args) => {
+            matches!(&func.kind, ExprKind::Ident(Ident(name)) if name == "unquote")
+                && args.len() == 1
+        }
+        _ => false,
+    }
+}
+
+/// quote `expr`, replacing each `unquote(arg)` in it with the value `eval_unquoted`
+/// returns for `arg`
+pub fn quote<E, F>(expr: Expr, mut eval_unquoted: F) -> Result<Object, E>
+where
+    E: From<EvalError>,
+    F: FnMut(Expr) -> Result<Object, E>,
+{
+    let expr = modify_expr(expr, &mut |expr: Expr| -> Result<Expr, E> {
+        if !is_unquote_call(&expr) {
+            return Ok(expr);
+        }
+
+        match expr.kind {
+            ExprKind::Call(_, mut args) => {
+                let obj = eval_unquoted(args.pop().expect("unquote takes one argument"))?;
+                Ok(object_to_expr(obj, expr.span)?)
+            }
+            _ => unreachable!(),
+        }
+    })?;
+    Ok(Object::Quote(expr))
+}
+
+/// arguments of `unquote` calls in `expr`, in the order `quote` evaluates them
+pub fn unquoted_args(expr: &Expr) -> Vec<Expr> {
+    let mut args = Vec::new();
+    let _ = modify_expr(expr.clone(), &mut |expr: Expr| -> Result<Expr, !> {
+        if is_unquote_call(&expr) {
+            if let ExprKind::Call(_, call_args) = &expr.kind {
+                args.push(call_args[0].clone());
+            }
+        }
+        Ok(expr)
+    });
+    args
+}
+
+fn object_to_expr(obj: Object, span: Span) -> EvalResult<Expr> {
+    let kind = match obj {
+        Object::Int(v) => ExprKind::Literal(Literal::Int(v)),
+        Object::BigInt(v) => ExprKind::Literal(Literal::BigInt(v)),
+        Object::Float(v) => ExprKind::Literal(Literal::Float(v)),
+        Object::Bool(v) => ExprKind::Literal(Literal::Bool(v)),
+        Object::Str(v) => ExprKind::Literal(Literal::Str(v)),
+        Object::Quote(expr) => return Ok(expr),
+        obj => {
+            return Err(
+                EvalError::new(format!("cannot unquote {} into AST node", obj.get_type()))
+                    .or_span(span),
+            )
+        }
+    };
+    Ok(Expr::new(kind, span))
+}
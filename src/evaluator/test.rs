@@ -1,16 +1,36 @@
 use super::*;
+use crate::ast::Program;
+use crate::compiler::Compiler;
 use crate::lexer::Lexer;
-use crate::object::{Env, EvalResult, Object};
+use crate::object::{Env, EvalResult, HashKey, Object, DEFAULT_MAX_CALL_DEPTH};
 use crate::parser::Parser;
+use crate::vm::Vm;
 
+/// evaluates input, checking that the VM agrees with the evaluator
 fn test_eval(input: &str) -> EvalResult<Object> {
-    let mut l = Lexer::new(input.to_string()).unwrap();
+    test_eval_with_max_call_depth(input, DEFAULT_MAX_CALL_DEPTH)
+}
+
+fn test_eval_with_max_call_depth(input: &str, max_call_depth: usize) -> EvalResult<Object> {
+    let mut l = Lexer::new(input);
     let mut p = Parser::new(&mut l);
     let program = p.parse_program();
     println!("{:?}", program);
-    let mut env = Env::new();
+    let env = Env::with_max_call_depth(max_call_depth);
+
+    let compiled = Compiler::new()
+        .compile(&program)
+        .and_then(|bytecode| Vm::with_max_call_depth(max_call_depth).run(bytecode));
+    let evaluated = eval(program, &env);
+    match (&evaluated, &compiled) {
+        (Ok(expected), Ok(got)) => {
+            assert_eq!(format!("{}", got), format!("{}", expected), "{}", input);
+            assert_eq!(got.get_type(), expected.get_type(), "{}", input);
+        }
+        _ => assert_eq!(compiled, evaluated, "{}", input),
+    }
 
-    eval(program, &mut env)
+    evaluated
 }
 
 #[test]
@@ -39,6 +59,140 @@ fn test_eval_int_expr() {
     }
 }
 
+#[test]
+fn test_eval_modulo_and_power() {
+    let test_cases = vec![
+        ("7 % 3", Object::Int(1)),
+        ("-7 % 3", Object::Int(-1)),
+        ("7 % -3", Object::Int(1)),
+        ("2 ** 10", Object::Int(1024)),
+        ("2 ** 3 ** 2", Object::Int(512)),
+        ("-2 ** 2", Object::Int(-4)),
+        ("(-2) ** 3", Object::Int(-8)),
+        ("3 * 2 ** 2", Object::Int(12)),
+        ("0 ** 0", Object::Int(1)),
+        ("2 ** -1", Object::Float(0.5)),
+        ("7.5 % 2", Object::Float(1.5)),
+        ("4 ** 0.5", Object::Float(2.0)),
+        ("(-9223372036854775807 - 1) % -1", Object::Int(0)),
+        ("100000000000000000000 % 7", Object::Int(2)),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated, EvalResult::Ok(expect), "{}", input);
+    }
+
+    match test_eval("2 ** 100") {
+        Ok(Object::BigInt(val)) => assert_eq!(val.to_string(), "1267650600228229401496703205376"),
+        result => panic!("expected big integer. got {:?}", result),
+    }
+}
+
+#[test]
+fn test_eval_float_expr() {
+    let test_cases = vec![
+        ("2.5", 2.5),
+        ("-2.5", -2.5),
+        ("1e-3", 0.001),
+        ("7 / 2.0", 3.5),
+        ("0.5 + 1", 1.5),
+        ("2 * 1.5 - 1", 2.0),
+        ("-(1.5 * 2)", -3.0),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated, EvalResult::Ok(Object::Float(expect)));
+    }
+
+    let test_cases = vec![
+        ("1.5 < 2", true),
+        ("2 > 1.5", true),
+        ("1.0 == 1", true),
+        ("0.5 != 0.5", false),
+        ("1 <= 1", true),
+        ("2 <= 1", false),
+        ("1 >= 1.5", false),
+        ("9223372036854775808 >= 9223372036854775807", true),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated, EvalResult::Ok(Object::Bool(expect)));
+    }
+
+    // division by zero follows IEEE 754 instead of failing
+    let test_cases = vec![
+        ("1 / 0.0", "inf"),
+        ("-1.5 / 0", "-inf"),
+        ("5 % 0.0", "NaN"),
+        ("5.5 % 0", "NaN"),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input).unwrap();
+        assert_eq!(format!("{}", evaluated), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_eval_big_int_expr() {
+    let test_cases = vec![
+        ("9223372036854775807 + 1", "9223372036854775808"),
+        ("-9223372036854775807 - 2", "-9223372036854775809"),
+        ("3037000500 * 3037000500", "9223372037000250000"),
+        ("(-9223372036854775807 - 1) / -1", "9223372036854775808"),
+        (
+            "let min = -9223372036854775807 - 1; -min",
+            "9223372036854775808",
+        ),
+        (
+            "123456789012345678901234567890",
+            "123456789012345678901234567890",
+        ),
+        (
+            "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(30)",
+            "265252859812191058636308480000000",
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        match test_eval(input) {
+            Ok(Object::BigInt(val)) => assert_eq!(val.to_string(), expect),
+            result => panic!("expected big integer. got {:?}", result),
+        }
+    }
+
+    // results fitting in i64 go back to plain integers
+    let test_cases = vec![
+        ("9223372036854775808 - 1", i64::MAX),
+        ("-9223372036854775808", i64::MIN),
+        ("100000000000000000000 / 100000000000000000000", 1),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated, EvalResult::Ok(Object::Int(expect)));
+    }
+
+    let test_cases = vec![
+        ("9223372036854775808 > 9223372036854775807", true),
+        ("9223372036854775808 == 9223372036854775807 + 1", true),
+        ("-9223372036854775809 < -1", true),
+        ("9223372036854775808 == 9223372036854775808.0", true),
+        (
+            r#"{9223372036854775808: true}[9223372036854775807 + 1]"#,
+            true,
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated, EvalResult::Ok(Object::Bool(expect)));
+    }
+}
+
 #[test]
 fn test_eval_bool_expr() {
     let test_cases = vec![
@@ -69,6 +223,29 @@ fn test_eval_bool_expr() {
     }
 }
 
+#[test]
+fn test_logical_operators() {
+    let test_cases = vec![
+        ("true && true", true),
+        ("true && false", false),
+        ("false || true", true),
+        ("false || false", false),
+        ("1 && \"a\"", true),
+        ("1 < 2 && 2 < 3", true),
+        ("false || 1 == 2", false),
+        ("if (false) { 1 } || true", true),
+        // right operand is not evaluated
+        ("false && undefined", false),
+        ("true || 1 / 0", true),
+        ("let f = fn(x) { x > 0 && f(x - 1) }; f(3)", false),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated, EvalResult::Ok(Object::Bool(expect)), "{}", input);
+    }
+}
+
 #[test]
 fn test_bang_operator() {
     let test_cases = vec![
@@ -138,6 +315,7 @@ fn test_return_stmt() {
 fn test_error_handling() {
     let test_cases = vec![
         ("5 + true;", "type mismatch: INT + BOOLEAN"),
+        ("1.5 + true;", "type mismatch: FLOAT + BOOLEAN"),
         ("5 + true; 5;", "type mismatch: INT + BOOLEAN"),
         ("-true", "unknown operator: -BOOLEAN"),
         ("5; true + false;", "unknown operator: BOOLEAN + BOOLEAN"),
@@ -157,11 +335,49 @@ fn test_error_handling() {
             "unknown operator: BOOLEAN + BOOLEAN",
         ),
         ("foobar", "identifier not found: foobar"),
+        (r#""Hello" - "World""#, "unknown operator: STRING - STRING"),
+        (r#""Hello" + 1"#, "type mismatch: STRING + INT"),
+        ("1[0]", "index operator not supported: INT"),
+        (r#"[1, 2]["0"]"#, "array index must be INT, got STRING"),
+        (
+            r#"{"name": "Monkey"}[fn(x) { x }];"#,
+            "unusable as hash key: FUNCTION",
+        ),
+        ("{[1]: 2}", "unusable as hash key: ARRAY"),
     ];
 
     for (input, expect) in test_cases {
         let evaluated = test_eval(input);
-        assert_eq!(evaluated, EvalResult::Err(EvalError(expect.to_string())))
+        assert_eq!(evaluated.map_err(|err| err.msg), Err(expect.to_string()))
+    }
+}
+
+#[test]
+fn test_error_location() {
+    let test_cases = vec![
+        ("5 + true;", "type mismatch: INT + BOOLEAN at 1:1"),
+        (
+            "let x = 1;\nlet y = x + true;",
+            "type mismatch: INT + BOOLEAN at 2:9",
+        ),
+        (
+            "let f = fn() {\n  -true\n};\nf();",
+            "unknown operator: -BOOLEAN at 2:3",
+        ),
+        ("len(1)", "argument to `len` not supported, got INT at 1:1"),
+        ("[1, 2][0][foo]", "identifier not found: foo at 1:11"),
+        (
+            "let a = 1;\nlet b = 0;\nlet c = 2 + a / b;",
+            "division by zero at 3:13",
+        ),
+        ("10000000000000000000 / (1 - 1)", "division by zero at 1:1"),
+        ("1 + 5 % 0", "division by zero at 1:5"),
+        ("2 ** 10000000000", "exponent too large: 10000000000 at 1:1"),
+    ];
+
+    for (input, expect) in test_cases {
+        let err = test_eval(input).expect_err("evaluation should fail");
+        assert_eq!(format!("{}", err), expect);
     }
 }
 
@@ -200,6 +416,10 @@ fn test_function_application() {
         ("let add = fn(x, y) { x + y; }; add(5, 5)", 10),
         ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5))", 20),
         ("fn(x) { x; }(5)", 5),
+        (
+            "let f = fn(a, b) { let c = a + b; c }; f(1, 2) + f(3, 4)",
+            10,
+        ),
     ];
 
     for (input, expect) in test_cases {
@@ -208,6 +428,31 @@ fn test_function_application() {
     }
 }
 
+#[test]
+fn test_function_arity() {
+    let test_cases = vec![
+        (
+            "let f = fn(x) { x }; f()",
+            "wrong number of arguments. got=0, want=1 at 1:22",
+        ),
+        (
+            "fn(a) { a }(1, 2)",
+            "wrong number of arguments. got=2, want=1 at 1:1",
+        ),
+        (
+            "let f = fn(x) { x };\nlet g = fn() { f(1, 2) };\ng()",
+            "wrong number of arguments. got=2, want=1 at 2:16",
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(
+            test_eval(input).map_err(|err| err.to_string()),
+            Err(expect.to_string())
+        );
+    }
+}
+
 #[test]
 fn test_closures() {
     let input = r#"
@@ -218,3 +463,532 @@ fn test_closures() {
 
     assert_eq!(test_eval(input), EvalResult::Ok(Object::Int(4)))
 }
+
+#[test]
+fn test_string_literal() {
+    let input = r#""Hello World!""#;
+
+    assert_eq!(
+        test_eval(input),
+        EvalResult::Ok(Object::Str("Hello World!".to_string()))
+    )
+}
+
+#[test]
+fn test_string_concatenation() {
+    let input = r#""Hello" + " " + "World!""#;
+
+    assert_eq!(
+        test_eval(input),
+        EvalResult::Ok(Object::Str("Hello World!".to_string()))
+    )
+}
+
+#[test]
+fn test_string_comparison() {
+    let test_cases = vec![
+        (r#""a" == "a""#, true),
+        (r#""a" == "b""#, false),
+        (r#""a" != "a""#, false),
+        (r#""a" != "b""#, true),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated, EvalResult::Ok(Object::Bool(expect)));
+    }
+}
+
+#[test]
+fn test_array_literal() {
+    let input = "[1, 2 * 2, 3 + 3]";
+
+    assert_eq!(
+        test_eval(input),
+        EvalResult::Ok(Object::Array(vec![
+            Object::Int(1),
+            Object::Int(4),
+            Object::Int(6)
+        ]))
+    )
+}
+
+#[test]
+fn test_array_index_expr() {
+    let test_cases = vec![
+        ("[1, 2, 3][0]", Some(1)),
+        ("[1, 2, 3][1]", Some(2)),
+        ("[1, 2, 3][2]", Some(3)),
+        ("let i = 0; [1][i];", Some(1)),
+        ("[1, 2, 3][1 + 1];", Some(3)),
+        ("let myArray = [1, 2, 3]; myArray[2];", Some(3)),
+        (
+            "let myArray = [1, 2, 3]; myArray[0] + myArray[1] + myArray[2];",
+            Some(6),
+        ),
+        (
+            "let myArray = [1, 2, 3]; let i = myArray[0]; myArray[i]",
+            Some(2),
+        ),
+        ("[1, 2, 3][3]", None),
+        ("[1, 2, 3][-1]", None),
+        ("[1, 2, 3][9223372036854775808]", None),
+        ("[1, 2, 3][-9223372036854775809]", None),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        match expect {
+            Some(v) => assert_eq!(evaluated, EvalResult::Ok(Object::Int(v))),
+            _ => assert_eq!(evaluated, EvalResult::Ok(Object::Null)),
+        }
+    }
+}
+
+#[test]
+fn test_hash_literal() {
+    let input = r#"
+        let two = "two";
+        {
+            "one": 10 - 9,
+            two: 1 + 1,
+            "thr" + "ee": 6 / 2,
+            4: 4,
+            true: 5,
+            false: 6
+        }
+    "#;
+
+    let mut expect = BTreeMap::new();
+    expect.insert(HashKey::Str("one".to_string()), Object::Int(1));
+    expect.insert(HashKey::Str("two".to_string()), Object::Int(2));
+    expect.insert(HashKey::Str("three".to_string()), Object::Int(3));
+    expect.insert(HashKey::Int(4), Object::Int(4));
+    expect.insert(HashKey::Bool(true), Object::Int(5));
+    expect.insert(HashKey::Bool(false), Object::Int(6));
+
+    assert_eq!(test_eval(input), EvalResult::Ok(Object::Hash(expect)))
+}
+
+#[test]
+fn test_hash_index_expr() {
+    let test_cases = vec![
+        (r#"{"foo": 5}["foo"]"#, Some(5)),
+        (r#"{"foo": 5}["bar"]"#, None),
+        (r#"let key = "foo"; {"foo": 5}[key]"#, Some(5)),
+        (r#"{}["foo"]"#, None),
+        ("{5: 5}[5]", Some(5)),
+        ("{true: 5}[true]", Some(5)),
+        ("{false: 5}[false]", Some(5)),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        match expect {
+            Some(v) => assert_eq!(evaluated, EvalResult::Ok(Object::Int(v))),
+            _ => assert_eq!(evaluated, EvalResult::Ok(Object::Null)),
+        }
+    }
+}
+
+#[test]
+fn test_builtin_functions() {
+    let test_cases = vec![
+        (r#"len("")"#, Ok(Object::Int(0))),
+        (r#"len("four")"#, Ok(Object::Int(4))),
+        (r#"len("hello world")"#, Ok(Object::Int(11))),
+        (r#"len("こんにちは")"#, Ok(Object::Int(5))),
+        ("len([1, 2, 3])", Ok(Object::Int(3))),
+        (r#"len({"a": 1})"#, Ok(Object::Int(1))),
+        (
+            "len(1)",
+            Err("argument to `len` not supported, got INT".to_string()),
+        ),
+        (
+            r#"len("one", "two")"#,
+            Err("wrong number of arguments. got=2, want=1".to_string()),
+        ),
+        ("type(1)", Ok(Object::Str("INT".to_string()))),
+        (r#"type("a")"#, Ok(Object::Str("STRING".to_string()))),
+        ("type(fn(x) { x })", Ok(Object::Str("FUNCTION".to_string()))),
+        ("type(len)", Ok(Object::Str("BUILTIN".to_string()))),
+        ("puts(1, 2)", Ok(Object::Null)),
+        ("let len = fn(x) { 42 }; len([])", Ok(Object::Int(42))),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(
+            test_eval(input).map_err(|err| err.msg),
+            expect,
+            "input: {}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_recursive_function() {
+    let test_cases = vec![
+        (
+            "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(5)",
+            120,
+        ),
+        (
+            r#"
+        let isEven = fn(n) { if (n == 0) { true } else { isOdd(n - 1) } };
+        let isOdd = fn(n) { if (n == 0) { false } else { isEven(n - 1) } };
+        if (isEven(10)) { 1 } else { 0 }
+        "#,
+            1,
+        ),
+        (
+            r#"
+        let outer = fn() {
+            let countDown = fn(n) { if (n == 0) { 0 } else { countDown(n - 1) } };
+            countDown(3)
+        };
+        outer()
+        "#,
+            0,
+        ),
+        // functions in a function body may use variables bound after them
+        (
+            r#"
+        fn() {
+            let isEven = fn(n) { if (n == 0) { true } else { isOdd(n - 1) } };
+            let isOdd = fn(n) { if (n == 0) { false } else { isEven(n - 1) } };
+            if (isEven(10)) { 1 } else { 0 }
+        }()
+        "#,
+            1,
+        ),
+        ("fn() { let g = fn() { y }; let y = 2; g() }()", 2),
+        (
+            "fn() { let f = fn() { fn() { z += 1 } }; let z = 1; f()(); f()(); z }()",
+            3,
+        ),
+        // the outer binding is used until the one of the function is made
+        ("let x = 5; fn() { let x = x + 1; fn() { x } }()()", 6),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(test_eval(input), EvalResult::Ok(Object::Int(expect)))
+    }
+}
+
+#[test]
+fn test_quote() {
+    let test_cases = vec![
+        ("quote(5)", "5"),
+        ("quote(5 + 8)", "(5 + 8)"),
+        ("quote(foobar)", "foobar"),
+        ("quote(foobar + barfoo)", "(foobar + barfoo)"),
+    ];
+
+    for (input, expect) in test_cases {
+        match test_eval(input) {
+            Ok(Object::Quote(expr)) => assert_eq!(format!("{}", expr), expect),
+            result => panic!("expected quote. got {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn test_quote_unquote() {
+    let test_cases = vec![
+        ("quote(unquote(4))", "4"),
+        ("quote(unquote(4 + 4))", "8"),
+        ("quote(8 + unquote(4 + 4))", "(8 + 8)"),
+        ("quote(unquote(4 + 4) + 8)", "(8 + 8)"),
+        ("let foobar = 8; quote(foobar)", "foobar"),
+        ("let foobar = 8; quote(unquote(foobar))", "8"),
+        ("quote(unquote(true))", "true"),
+        ("quote(unquote(true == false))", "false"),
+        ("quote(unquote(quote(4 + 4)))", "(4 + 4)"),
+        (
+            "let quotedInfixExpr = quote(4 + 4); quote(unquote(4 + 4) + unquote(quotedInfixExpr))",
+            "(8 + (4 + 4))",
+        ),
+        ("let f = fn(x) { quote(unquote(x) * 2) }; f(3)", "(3 * 2)"),
+    ];
+
+    for (input, expect) in test_cases {
+        match test_eval(input) {
+            Ok(Object::Quote(expr)) => assert_eq!(format!("{}", expr), expect),
+            result => panic!("expected quote. got {:?}", result),
+        }
+    }
+
+    let err = test_eval("quote(unquote(fn(x) { x }))").unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "cannot unquote FUNCTION into AST node at 1:7"
+    );
+}
+
+fn parse(input: &str) -> Program {
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+    assert!(p.errors.is_empty(), "{:?}", p.errors);
+    program
+}
+
+#[test]
+fn test_define_macros() {
+    let input = r#"
+    let number = 1;
+    let function = fn(x, y) { x + y };
+    let mymacro = macro(x, y) { x + y; };
+    "#;
+
+    let env = Env::new();
+    let mut program = parse(input);
+    define_macros(&mut program, &env);
+
+    assert_eq!(program.statements.len(), 2);
+    assert_eq!(env.get("number"), None);
+    assert_eq!(env.get("function"), None);
+    match env.get("mymacro") {
+        Some(Object::Macro(mac)) => {
+            assert_eq!(
+                format!("{}", Object::Macro(mac)),
+                "macro (x, y) { (x + y) }"
+            )
+        }
+        obj => panic!("expected macro. got {:?}", obj),
+    }
+}
+
+#[test]
+fn test_expand_macros() {
+    let test_cases = vec![
+        (
+            "let infixExpr = macro() { quote(1 + 2); }; infixExpr();",
+            "(1 + 2)",
+        ),
+        (
+            "let reverse = macro(a, b) { quote(unquote(b) - unquote(a)); }; reverse(2 + 2, 10 - 5);",
+            "(10 - 5) - (2 + 2)",
+        ),
+        (
+            r#"
+            let unless = macro(condition, consequence, alternative) {
+                quote(if (!(unquote(condition))) {
+                    unquote(consequence);
+                } else {
+                    unquote(alternative);
+                });
+            };
+            unless(10 > 5, puts("not greater"), puts("greater"));
+            "#,
+            r#"if (!(10 > 5)) { puts("not greater") } else { puts("greater") }"#,
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        let env = Env::new();
+        let mut program = parse(input);
+        define_macros(&mut program, &env);
+        let expanded = expand_macros(program, &env).unwrap();
+
+        assert_eq!(format!("{}", expanded), format!("{}", parse(expect)));
+    }
+
+    let env = Env::new();
+    let mut program = parse("let m = macro() { 1 };\nm()");
+    define_macros(&mut program, &env);
+    let err = expand_macros(program, &env).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "macro must return quoted AST node, got INT at 2:1"
+    );
+
+    let env = Env::new();
+    let mut program = parse("let m = macro(a) { a };\nm(1, 2)");
+    define_macros(&mut program, &env);
+    let err = expand_macros(program, &env).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "wrong number of arguments. got=2, want=1 at 2:1"
+    );
+}
+
+#[test]
+fn test_tail_calls() {
+    let test_cases = vec![
+        (
+            "let sum = fn(n, acc) { if (n == 0) { acc } else { sum(n - 1, acc + n) } }; sum(20000, 0)",
+            200010000,
+        ),
+        (
+            "let sum = fn(n, acc) { if (n == 0) { return acc; } return sum(n - 1, acc + n); }; sum(20000, 0)",
+            200010000,
+        ),
+        (
+            r#"
+        let isEven = fn(n) { if (n == 0) { true } else { isOdd(n - 1) } };
+        let isOdd = fn(n) { if (n == 0) { false } else { isEven(n - 1) } };
+        if (isEven(20001)) { 1 } else { 0 }
+        "#,
+            0,
+        ),
+        (
+            r#"
+        let count = fn(n) {
+            if (n > 0) {
+                let m = n - 1;
+                if (m > 0) { return count(m); }
+                count(0)
+            } else {
+                len("done")
+            }
+        };
+        count(20000)
+        "#,
+            4,
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(test_eval(input), EvalResult::Ok(Object::Int(expect)))
+    }
+}
+
+#[test]
+fn test_call_depth() {
+    let test_cases = vec![
+        (
+            "let f = fn(n) { 1 + f(n + 1) }; f(0)",
+            Err("maximum call depth (20) exceeded in f at 1:21"),
+        ),
+        (
+            "fn(n) { let g = fn(n) { if (n > 0) { 1 + g(n - 1) } }; g(n) }(30)",
+            Err("maximum call depth (20) exceeded in g at 1:42"),
+        ),
+        (
+            "let f = fn(g) { 1 + g(g) }; f(fn(g) { 1 + g(g) })",
+            Err("maximum call depth (20) exceeded in anonymous function at 1:43"),
+        ),
+        (
+            "let f = fn(n) { if (n > 1) { 1 + f(n - 1) } else { 1 } }; f(20)",
+            Ok(20),
+        ),
+        // calls in tail position do not nest
+        (
+            "let f = fn(n) { if (n > 0) { f(n - 1) } else { 0 } }; f(100)",
+            Ok(0),
+        ),
+        (
+            "let f = fn(n) { if (n > 1) { 1 + f(n - 1) } else { 1 } }; f(20) + f(20)",
+            Ok(40),
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval_with_max_call_depth(input, 20);
+        match expect {
+            Ok(expect) => assert_eq!(evaluated, Ok(Object::Int(expect)), "{}", input),
+            Err(msg) => assert_eq!(format!("{}", evaluated.unwrap_err()), msg, "{}", input),
+        }
+    }
+}
+
+#[test]
+fn test_assignment() {
+    let test_cases =
+        vec![
+        ("let x = 1; x = 2; x", Ok(2)),
+        ("let x = 1; x = x + 2", Ok(3)),
+        ("let x = 1; let y = 2; x = y = 3; x + y", Ok(6)),
+        ("let x = 10; x += 2; x -= 4; x *= 3; x /= 6; x", Ok(4)),
+        (
+            "let counter = fn() { let n = 0; fn() { n += 1 } }; let c = counter(); c(); c(); c()",
+            Ok(3),
+        ),
+        (
+            "let c = fn() { let n = 0; fn() { n += 1 } }; let a = c(); let b = c(); a(); a(); b()",
+            Ok(1),
+        ),
+        // a closure sees assignments made after it was created
+        ("fn(n) { let get = fn() { n }; n = 5; get() }(1)", Ok(5)),
+        (
+            "let f = fn() { let n = 1; let g = fn() { fn() { n *= 2 } }; g()(); g()(); n }; f()",
+            Ok(4),
+        ),
+        ("let n = 1; let add = fn(x) { n += x }; add(2); add(3); n", Ok(6)),
+        ("fn() { let f = fn() { f = 7 }; f(); f }()", Ok(7)),
+        ("x = 1", Err("assignment to undeclared variable: x at 1:1")),
+        (
+            "let f = fn() { y = 1 }; f()",
+            Err("assignment to undeclared variable: y at 1:16"),
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        let evaluated = test_eval(input);
+        match expect {
+            Ok(expect) => assert_eq!(evaluated, Ok(Object::Int(expect)), "{}", input),
+            Err(msg) => assert_eq!(format!("{}", evaluated.unwrap_err()), msg, "{}", input),
+        }
+    }
+}
+
+#[test]
+fn test_while_loops() {
+    let test_cases = vec![
+        ("let i = 0; while (i < 5) { i += 1 }; i", 5),
+        ("let i = 0; while (false) { i = 1 }; i", 0),
+        (
+            "let i = 0; let n = 0; while (true) { i += 1; if (i > 9) { break; } if (i % 3 == 0) { continue } n += i; } n",
+            27,
+        ),
+        // `break` leaves the innermost loop only
+        (
+            "let n = 0; let a = 0; while (a < 3) { a += 1; let b = 0; while (true) { b += 1; if (b > a) { break } n += 1 } } n",
+            6,
+        ),
+        (
+            "let f = fn(n) { let i = 0; while (true) { i += 1; if (i == n) { return i * 10 } } }; f(4)",
+            40,
+        ),
+        // a loop in a function body does not stop its caller's loop
+        (
+            "let f = fn() { while (true) { break } 1 }; let n = 0; while (n < 3) { n += f() } n",
+            3,
+        ),
+        // closures made in a loop see the binding of the last iteration
+        (
+            "let f = fn() { let g = 0; let i = 0; while (i < 3) { let j = i; if (i == 0) { g = fn() { j } } i += 1 } g() }; f()",
+            2,
+        ),
+        ("let i = 0; while (i < 100000) { i += 1 }; i", 100000),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(test_eval(input), Ok(Object::Int(expect)), "{}", input);
+    }
+    assert_eq!(
+        test_eval("let i = 0; while (i < 1) { i += 1 }"),
+        Ok(Object::Null)
+    );
+}
+
+#[test]
+fn test_break_outside_loop() {
+    // the parser rejects it, so replace the body of `f` after parsing
+    let input = "let f = fn() { 1 }; while (true) { f() }";
+    let mut program = parse(input);
+    if let StmtKind::Let(_, expr) = &mut program.statements[0].kind {
+        if let ExprKind::Function(_, body) = &mut expr.kind {
+            body[0] = Stmt::new(StmtKind::Break, body[0].span);
+        }
+    }
+
+    let expect = "'break' outside loop at 1:16";
+    let compiled = Compiler::new().compile(&program);
+    assert_eq!(format!("{}", compiled.unwrap_err()), expect);
+    // does not stop the loop the function is called in
+    let evaluated = eval(program, &Env::new());
+    assert_eq!(format!("{}", evaluated.unwrap_err()), expect);
+}
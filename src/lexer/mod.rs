@@ -1,136 +1,338 @@
-use crate::token::Token;
-use ascii::{AsciiChar, AsciiString, FromAsciiError};
+use crate::token::{Pos, Span, Token};
 
 #[cfg(test)]
 mod test;
 
+/// Splits source into tokens. Identifiers and strings borrow their text from it.
+///
+/// Also iterates over the tokens alone, ending after `EOF`.
 #[derive(Debug)]
-pub struct Lexer {
-    input: AsciiString,
-    position: usize,
-    read_position: usize,
-    ch: AsciiChar,
+pub struct Lexer<'a> {
+    input: &'a str,
+    position: usize,      // byte offset of `ch`
+    read_position: usize, // byte offset of the character after `ch`
+    ch: char,
+    pos: Pos,       // location of `ch`
+    finished: bool, // `EOF` was returned by the iterator
 }
 
-impl Lexer {
-    pub fn new(input: String) -> Result<Lexer, FromAsciiError<String>> {
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
         let mut l = Lexer {
-            input: AsciiString::from_ascii(input)?,
+            input,
             position: 0,
             read_position: 0,
-            ch: AsciiChar::Null,
+            ch: '\0',
+            pos: Pos { line: 1, column: 0 },
+            finished: false,
         };
         l.read_char();
-        Ok(l)
+        l
     }
 
     fn read_char(&mut self) {
-        if let Some(ch) = self.input.chars().nth(self.read_position) {
-            self.ch = ch
+        if self.ch == '\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
         } else {
-            self.ch = AsciiChar::Null
+            self.pos.column += 1;
         }
         self.position = self.read_position;
-        self.read_position += 1
+        self.ch = match self.input[self.read_position..].chars().next() {
+            Some(ch) => {
+                self.read_position += ch.len_utf8();
+                ch
+            }
+            None => '\0',
+        };
     }
 
-    pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+    /// returns next token with its location in source
+    pub fn next_token(&mut self) -> (Token<'a>, Span) {
+        if let Some(span) = self.skip_whitespace() {
+            return (Token::UNTERMINATEDCOMMENT, span);
+        }
+
+        let start = self.pos;
+        let tok = self.read_token();
+        (
+            tok,
+            Span {
+                start,
+                end: self.pos,
+            },
+        )
+    }
 
-        let tok: Token;
+    fn read_token(&mut self) -> Token<'a> {
+        let tok: Token<'a>;
         match self.ch {
-            AsciiChar::Equal => {
-                if self.peek_char() == AsciiChar::Equal {
-                    let current_ch = self.ch;
+            '=' => {
+                if self.peek_char() == '=' {
                     self.read_char();
-                    let mut literal = AsciiString::new();
-                    literal.push(current_ch);
-                    literal.push(self.ch);
                     tok = Token::EQ
                 } else {
                     tok = Token::ASSIGN
                 }
             }
-            AsciiChar::Semicolon => tok = Token::SEMICOLON,
-            AsciiChar::ParenOpen => tok = Token::LPAREN,
-            AsciiChar::ParenClose => tok = Token::RPAREN,
-            AsciiChar::Comma => tok = Token::COMMA,
-            AsciiChar::CurlyBraceOpen => tok = Token::LBRACE,
-            AsciiChar::CurlyBraceClose => tok = Token::RBRACE,
-            AsciiChar::Plus => tok = Token::PLUS,
-            AsciiChar::Minus => tok = Token::MINUS,
-            AsciiChar::Exclamation => {
-                if self.peek_char() == AsciiChar::Equal {
-                    let current_ch = self.ch;
+            ';' => tok = Token::SEMICOLON,
+            ':' => tok = Token::COLON,
+            '(' => tok = Token::LPAREN,
+            ')' => tok = Token::RPAREN,
+            ',' => tok = Token::COMMA,
+            '{' => tok = Token::LBRACE,
+            '}' => tok = Token::RBRACE,
+            '[' => tok = Token::LBRACKET,
+            ']' => tok = Token::RBRACKET,
+            '+' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    tok = Token::PLUSASSIGN
+                } else {
+                    tok = Token::PLUS
+                }
+            }
+            '-' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    tok = Token::MINUSASSIGN
+                } else {
+                    tok = Token::MINUS
+                }
+            }
+            '!' => {
+                if self.peek_char() == '=' {
                     self.read_char();
-                    let mut literal = AsciiString::new();
-                    literal.push(current_ch);
-                    literal.push(self.ch);
                     tok = Token::NOTEQ;
                 } else {
                     tok = Token::BANG;
                 }
             }
-            AsciiChar::Slash => tok = Token::SLASH,
-            AsciiChar::Asterisk => tok = Token::ASTERISK,
-            AsciiChar::LessThan => tok = Token::LT,
-            AsciiChar::GreaterThan => tok = Token::GT,
+            '&' if self.peek_char() == '&' => {
+                self.read_char();
+                tok = Token::AND
+            }
+            '|' if self.peek_char() == '|' => {
+                self.read_char();
+                tok = Token::OR
+            }
+            '/' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    tok = Token::SLASHASSIGN
+                } else {
+                    tok = Token::SLASH
+                }
+            }
+            '*' => {
+                if self.peek_char() == '*' {
+                    self.read_char();
+                    tok = Token::POWER
+                } else if self.peek_char() == '=' {
+                    self.read_char();
+                    tok = Token::ASTERISKASSIGN
+                } else {
+                    tok = Token::ASTERISK
+                }
+            }
+            '%' => tok = Token::PERCENT,
+            '<' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    tok = Token::LTEQ
+                } else {
+                    tok = Token::LT
+                }
+            }
+            '>' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    tok = Token::GTEQ
+                } else {
+                    tok = Token::GT
+                }
+            }
+            '"' => {
+                tok = match self.read_string() {
+                    Some(val) => Token::STRING(val.into()),
+                    None => Token::UNTERMINATEDSTRING,
+                }
+            }
             _ => {
                 if self.is_letter() {
                     let ident = self.read_identifier();
-                    return match ident.as_str() {
+                    return match ident {
                         "fn" => Token::FUNCTION,
+                        "macro" => Token::MACRO,
                         "let" => Token::LET,
                         "true" => Token::TRUE,
                         "false" => Token::FALSE,
                         "if" => Token::IF,
                         "else" => Token::ELSE,
                         "return" => Token::RETURN,
-                        _ => Token::IDENT(ident),
+                        "while" => Token::WHILE,
+                        "break" => Token::BREAK,
+                        "continue" => Token::CONTINUE,
+                        _ => Token::IDENT(ident.into()),
                     };
                 } else if self.ch.is_ascii_digit() {
-                    return Token::INT(self.read_number());
-                };
-                tok = Token::EOF
+                    return self.read_number();
+                } else if self.at_end() {
+                    tok = Token::EOF
+                } else {
+                    // e.g. `@`, `\0`, or `&` not followed by another one. non-ASCII
+                    // characters are allowed only in identifiers and strings
+                    tok = Token::ILLEGAL(self.ch.to_string())
+                }
             }
         };
         self.read_char();
         tok
     }
 
-    fn read_identifier(&mut self) -> String {
-        let mut literal = AsciiString::new();
+    fn read_identifier(&mut self) -> &'a str {
+        let start = self.position;
         while self.is_letter() {
-            literal.push(self.ch);
             self.read_char();
         }
-        literal.to_string()
+        &self.input[start..self.position]
+    }
+
+    /// reads integer or float literal like `3`, `3.14` or `1e-3`
+    fn read_number(&mut self) -> Token<'a> {
+        let start = self.position;
+        let mut is_float = false;
+        self.read_digits();
+
+        if self.ch == '.' && self.peek_char().is_ascii_digit() {
+            is_float = true;
+            self.read_char();
+            self.read_digits();
+        }
+
+        let has_exponent = match self.peek_char() {
+            '+' | '-' => self.peek_char_at(1).is_ascii_digit(),
+            c => c.is_ascii_digit(),
+        };
+        if (self.ch == 'e' || self.ch == 'E') && has_exponent {
+            is_float = true;
+            self.read_char();
+            if self.ch == '+' || self.ch == '-' {
+                self.read_char();
+            }
+            self.read_digits();
+        }
+
+        let literal = &self.input[start..self.position];
+        if is_float {
+            Token::FLOAT(literal.parse().expect("failed to parse number"))
+        } else {
+            match literal.parse() {
+                Ok(val) => Token::INT(val),
+                Err(_) => Token::BIGINT(literal.parse().expect("failed to parse number")),
+            }
+        }
     }
 
-    fn read_number(&mut self) -> i64 {
-        let mut literal = AsciiString::new();
+    fn read_digits(&mut self) {
         while self.ch.is_ascii_digit() {
-            literal.push(self.ch);
             self.read_char();
         }
-        literal.to_string().parse().expect("failed to parse number")
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.ch.is_ascii_blank() || self.ch.is_whitespace() {
-            self.read_char()
+    /// reads contents of a string literal. returns `None` if it has no closing quote
+    fn read_string(&mut self) -> Option<&'a str> {
+        self.read_char(); // skip opening quote
+        let start = self.position;
+        while self.ch != '"' {
+            if self.at_end() {
+                return None;
+            }
+            self.read_char();
+        }
+        Some(&self.input[start..self.position])
+    }
+
+    /// skips whitespace and comments. returns the span of a block comment
+    /// still open at the end of input
+    fn skip_whitespace(&mut self) -> Option<Span> {
+        loop {
+            match (self.ch, self.peek_char()) {
+                (ch, _) if ch.is_whitespace() => self.read_char(),
+                ('/', '/') => {
+                    while self.ch != '\n' && !self.at_end() {
+                        self.read_char()
+                    }
+                }
+                ('/', '*') => {
+                    let start = self.pos;
+                    if !self.skip_block_comment() {
+                        return Some(Span {
+                            start,
+                            end: self.pos,
+                        });
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// skips `/* */` comment, which may contain other ones. returns whether it was closed
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            match (self.ch, self.peek_char()) {
+                _ if self.at_end() => return false,
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    if depth == 0 {
+                        self.read_char();
+                        return true;
+                    }
+                }
+                _ => {}
+            }
+            self.read_char();
         }
     }
 
+    /// whether `ch` is past the input, rather than `\0` in it
+    fn at_end(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
     fn is_letter(&self) -> bool {
-        self.ch.is_alphabetic() || self.ch == AsciiChar::UnderScore
+        self.ch.is_alphabetic() || self.ch == '_'
     }
 
-    fn peek_char(&mut self) -> AsciiChar {
-        if let Some(ch) = self.input.chars().nth(self.read_position) {
-            ch
-        } else {
-            AsciiChar::Null
+    fn peek_char(&self) -> char {
+        self.peek_char_at(0)
+    }
+
+    /// returns the character `offset` places after the next one
+    fn peek_char_at(&self, offset: usize) -> char {
+        self.input[self.read_position..]
+            .chars()
+            .nth(offset)
+            .unwrap_or('\0')
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.finished {
+            return None;
         }
+        let (tok, _) = self.next_token();
+        self.finished = tok == Token::EOF;
+        Some(tok)
     }
 }
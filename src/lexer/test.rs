@@ -1,4 +1,5 @@
 use super::*;
+use std::borrow::Cow;
 
 #[test]
 fn test_next_token() {
@@ -11,7 +12,7 @@ fn test_next_token() {
             };
 
             let result = add(five, ten);
-            !-/*5;
+            !-/ *5;
             5 < 10 > 5;
 
             if (5 < 10) {
@@ -22,44 +23,51 @@ fn test_next_token() {
 
             10 == 10;
             10 != 9;
-            "###
-    .to_string();
+            "foobar"
+            "foo bar"
+            [1, 2];
+            {"foo": "bar"}
+            a && b || c
+            1 <= 2 >= 3 % 4 ** 5
+            x += 1 -= 2 *= 3 /= 4
+            while break continue
+            "###;
 
     let expected = vec![
         Token::LET,
-        Token::IDENT("five".to_string()),
+        Token::IDENT("five".into()),
         Token::ASSIGN,
         Token::INT(5),
         Token::SEMICOLON,
         Token::LET,
-        Token::IDENT("ten".to_string()),
+        Token::IDENT("ten".into()),
         Token::ASSIGN,
         Token::INT(10),
         Token::SEMICOLON,
         Token::LET,
-        Token::IDENT("add".to_string()),
+        Token::IDENT("add".into()),
         Token::ASSIGN,
         Token::FUNCTION,
         Token::LPAREN,
-        Token::IDENT("x".to_string()),
+        Token::IDENT("x".into()),
         Token::COMMA,
-        Token::IDENT("y".to_string()),
+        Token::IDENT("y".into()),
         Token::RPAREN,
         Token::LBRACE,
-        Token::IDENT("x".to_string()),
+        Token::IDENT("x".into()),
         Token::PLUS,
-        Token::IDENT("y".to_string()),
+        Token::IDENT("y".into()),
         Token::SEMICOLON,
         Token::RBRACE,
         Token::SEMICOLON,
         Token::LET,
-        Token::IDENT("result".to_string()),
+        Token::IDENT("result".into()),
         Token::ASSIGN,
-        Token::IDENT("add".to_string()),
+        Token::IDENT("add".into()),
         Token::LPAREN,
-        Token::IDENT("five".to_string()),
+        Token::IDENT("five".into()),
         Token::COMMA,
-        Token::IDENT("ten".to_string()),
+        Token::IDENT("ten".into()),
         Token::RPAREN,
         Token::SEMICOLON,
         Token::BANG,
@@ -99,14 +107,248 @@ fn test_next_token() {
         Token::NOTEQ,
         Token::INT(9),
         Token::SEMICOLON,
+        Token::STRING("foobar".into()),
+        Token::STRING("foo bar".into()),
+        Token::LBRACKET,
+        Token::INT(1),
+        Token::COMMA,
+        Token::INT(2),
+        Token::RBRACKET,
+        Token::SEMICOLON,
+        Token::LBRACE,
+        Token::STRING("foo".into()),
+        Token::COLON,
+        Token::STRING("bar".into()),
+        Token::RBRACE,
+        Token::IDENT("a".into()),
+        Token::AND,
+        Token::IDENT("b".into()),
+        Token::OR,
+        Token::IDENT("c".into()),
+        Token::INT(1),
+        Token::LTEQ,
+        Token::INT(2),
+        Token::GTEQ,
+        Token::INT(3),
+        Token::PERCENT,
+        Token::INT(4),
+        Token::POWER,
+        Token::INT(5),
+        Token::IDENT("x".into()),
+        Token::PLUSASSIGN,
+        Token::INT(1),
+        Token::MINUSASSIGN,
+        Token::INT(2),
+        Token::ASTERISKASSIGN,
+        Token::INT(3),
+        Token::SLASHASSIGN,
+        Token::INT(4),
+        Token::WHILE,
+        Token::BREAK,
+        Token::CONTINUE,
+        Token::EOF,
+    ];
+
+    let mut l = Lexer::new(input);
+
+    for expected_token in expected {
+        let (tok, _) = l.next_token();
+
+        assert_eq!(tok, expected_token);
+    }
+}
+
+#[test]
+fn test_token_span() {
+    let input = "let x = 10;\n  \"ab\" == y";
+
+    let expected = vec![
+        (Token::LET, (1, 1), (1, 4)),
+        (Token::IDENT("x".into()), (1, 5), (1, 6)),
+        (Token::ASSIGN, (1, 7), (1, 8)),
+        (Token::INT(10), (1, 9), (1, 11)),
+        (Token::SEMICOLON, (1, 11), (1, 12)),
+        (Token::STRING("ab".into()), (2, 3), (2, 7)),
+        (Token::EQ, (2, 8), (2, 10)),
+        (Token::IDENT("y".into()), (2, 11), (2, 12)),
+    ];
+
+    let mut l = Lexer::new(input);
+
+    for (expected_token, (start_line, start_col), (end_line, end_col)) in expected {
+        let (tok, span) = l.next_token();
+
+        assert_eq!(tok, expected_token);
+        assert_eq!(
+            span,
+            Span {
+                start: Pos {
+                    line: start_line,
+                    column: start_col
+                },
+                end: Pos {
+                    line: end_line,
+                    column: end_col
+                },
+            }
+        );
+    }
+}
+
+#[test]
+fn test_utf8_input() {
+    let input = "let 名前 = \"こんにちは, 世界\";\nname → 1";
+
+    let expected = vec![
+        (Token::LET, (1, 1)),
+        (Token::IDENT("名前".into()), (1, 5)),
+        (Token::ASSIGN, (1, 8)),
+        (Token::STRING("こんにちは, 世界".into()), (1, 10)),
+        (Token::SEMICOLON, (1, 21)),
+        (Token::IDENT("name".into()), (2, 1)),
+        (Token::ILLEGAL("→".to_string()), (2, 6)),
+        (Token::INT(1), (2, 8)),
+        (Token::EOF, (2, 9)),
+    ];
+
+    let mut l = Lexer::new(input);
+
+    for (expected_token, (line, column)) in expected {
+        let (tok, span) = l.next_token();
+
+        assert_eq!(tok, expected_token);
+        assert_eq!(span.start, Pos { line, column });
+    }
+}
+
+#[test]
+fn test_number_literals() {
+    let input = "3 3.25 1e-3 2.5E+2 7e2 4e 9223372036854775808";
+
+    let expected = vec![
+        Token::INT(3),
+        Token::FLOAT(3.25),
+        Token::FLOAT(0.001),
+        Token::FLOAT(250.0),
+        Token::FLOAT(700.0),
+        Token::INT(4),
+        Token::IDENT("e".into()),
+        Token::BIGINT("9223372036854775808".parse().unwrap()),
         Token::EOF,
     ];
 
-    let mut l = Lexer::new(input).unwrap();
+    let mut l = Lexer::new(input);
 
     for expected_token in expected {
-        let tok = l.next_token();
+        assert_eq!(l.next_token().0, expected_token);
+    }
+}
+
+#[test]
+fn test_comments() {
+    let input = r#"
+    // comment
+    let x = 1; // trailing comment
+    /* block
+       /* nested */ still comment */
+    x /**/ / 2 //
+    /* /*/ */ */ "// not a comment"
+    "#;
+
+    let expected = vec![
+        (Token::LET, (3, 5)),
+        (Token::IDENT("x".into()), (3, 9)),
+        (Token::ASSIGN, (3, 11)),
+        (Token::INT(1), (3, 13)),
+        (Token::SEMICOLON, (3, 14)),
+        (Token::IDENT("x".into()), (6, 5)),
+        (Token::SLASH, (6, 12)),
+        (Token::INT(2), (6, 14)),
+        (Token::STRING("// not a comment".into()), (7, 18)),
+        (Token::EOF, (8, 5)),
+    ];
+
+    let mut l = Lexer::new(input);
+
+    for (expected_token, (line, column)) in expected {
+        let (tok, span) = l.next_token();
 
         assert_eq!(tok, expected_token);
+        assert_eq!(span.start, Pos { line, column });
     }
 }
+
+#[test]
+fn test_unterminated_comment() {
+    let mut l = Lexer::new("1 /* a /* b */ c\n");
+
+    assert_eq!(l.next_token().0, Token::INT(1));
+    let (tok, span) = l.next_token();
+    assert_eq!(tok, Token::UNTERMINATEDCOMMENT);
+    assert_eq!(span.start, Pos { line: 1, column: 3 });
+    assert_eq!(l.next_token().0, Token::EOF);
+}
+
+#[test]
+fn test_unterminated_string() {
+    let mut l = Lexer::new("1 \"a\nb");
+
+    assert_eq!(l.next_token().0, Token::INT(1));
+    let (tok, span) = l.next_token();
+    assert_eq!(tok, Token::UNTERMINATEDSTRING);
+    assert_eq!(span.start, Pos { line: 1, column: 3 });
+    assert_eq!(l.next_token().0, Token::EOF);
+}
+
+#[test]
+fn test_illegal_characters() {
+    let input = "5 @ 3 & $x | ~\0 \"a\0b\" // \0\n1";
+
+    let expected = vec![
+        (Token::INT(5), (1, 1)),
+        (Token::ILLEGAL("@".to_string()), (1, 3)),
+        (Token::INT(3), (1, 5)),
+        (Token::ILLEGAL("&".to_string()), (1, 7)),
+        (Token::ILLEGAL("$".to_string()), (1, 9)),
+        (Token::IDENT("x".into()), (1, 10)),
+        (Token::ILLEGAL("|".to_string()), (1, 12)),
+        (Token::ILLEGAL("~".to_string()), (1, 14)),
+        // NUL is not the end of input
+        (Token::ILLEGAL("\0".to_string()), (1, 15)),
+        (Token::STRING("a\0b".into()), (1, 17)),
+        (Token::INT(1), (2, 1)),
+        (Token::EOF, (2, 2)),
+    ];
+
+    let mut l = Lexer::new(input);
+
+    for (expected_token, (line, column)) in expected {
+        let (tok, span) = l.next_token();
+
+        assert_eq!(tok, expected_token);
+        assert_eq!(span.start, Pos { line, column });
+    }
+}
+
+#[test]
+fn test_iterator() {
+    let input = "let x = \"a\";";
+
+    let tokens: Vec<Token> = Lexer::new(input).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::LET,
+            Token::IDENT("x".into()),
+            Token::ASSIGN,
+            Token::STRING("a".into()),
+            Token::SEMICOLON,
+            Token::EOF,
+        ]
+    );
+    assert!(matches!(tokens[1], Token::IDENT(Cow::Borrowed("x"))));
+
+    let mut l = Lexer::new("");
+    assert_eq!(l.next(), Some(Token::EOF));
+    assert_eq!(l.next(), None);
+}
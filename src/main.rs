@@ -1,23 +1,90 @@
 #![feature(never_type)]
 #![feature(box_patterns)]
 mod ast;
+mod builtins;
+mod code;
+mod compiler;
 mod evaluator;
 mod lexer;
 mod object;
 mod parser;
 mod repl;
+mod runner;
 mod token;
+mod vm;
 
+use runner::{Config, Engine};
+use std::env;
 use std::io;
+use std::path::Path;
+use std::process;
+use std::thread;
 
 #[cfg_attr(tarpaulin, skip)]
 fn main() {
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let config = match parse_flags(&flags) {
+        Ok(config) => config,
+        Err(msg) => {
+            eprintln!("[ERROR] {}", msg);
+            process::exit(2);
+        }
+    };
+
+    // the main thread's stack is too small for deep recursion in the evaluator
+    let runner = thread::Builder::new()
+        .stack_size(config.stack_size())
+        .spawn(move || start(config, args.first()));
+    let runner = match runner {
+        Ok(runner) => runner,
+        Err(err) => {
+            eprintln!(
+                "[ERROR] cannot allocate stack for maximum call depth {}: {}",
+                config.max_call_depth, err
+            );
+            process::exit(1);
+        }
+    };
+    if runner.join().is_err() {
+        process::exit(101);
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+fn parse_flags(flags: &[String]) -> Result<Config, String> {
+    let mut config = Config::new(Engine::Eval);
+    for flag in flags {
+        if flag == "--vm" {
+            config.engine = Engine::Vm;
+        } else if let Some(depth) = flag.strip_prefix("--max-call-depth=") {
+            config.max_call_depth = depth
+                .parse()
+                .map_err(|_| format!("invalid maximum call depth: {}", depth))?;
+        } else {
+            return Err(format!("unknown option: {}", flag));
+        }
+    }
+    Ok(config)
+}
+
+#[cfg_attr(tarpaulin, skip)]
+fn start(config: Config, path: Option<&String>) {
+    if let Some(path) = path {
+        if let Err(err) = runner::run_file(Path::new(path), config) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        return;
+    }
+
     println!("Hello! This is the Monky programming language!");
     println!("Feel free to type in commands!");
     let stdin = io::stdin();
     let stdin_lock = stdin.lock();
-    if let Err(err) = repl::start(stdin_lock, io::stdout()) {
+    if let Err(err) = repl::start(stdin_lock, io::stdout(), config) {
         eprintln!("[ERROR] failed to read line");
         eprintln!("{:?}", err);
+        process::exit(1);
     }
 }
@@ -1,13 +1,33 @@
-use crate::ast::{BlockStmt, Ident};
-use std::collections::HashMap;
+use crate::ast::{BlockStmt, Expr, Ident, Stmt};
+use crate::code::{Instructions, SourceMap};
+use crate::token::Span;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::rc::{Rc, Weak};
+
+#[cfg(test)]
+mod test;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Object {
     Int(i64),
+    BigInt(BigInt), // only holds values out of the range of `Int`
+    Float(f64),
     Bool(bool),
+    Str(String),
+    Array(Vec<Object>),
+    Hash(BTreeMap<HashKey, Object>),
     Return(Box<Object>),
     Func(Func),
+    Builtin(Builtin),
+    CompiledFunction(Rc<CompiledFunction>),
+    Closure(Rc<Closure>),
+    Macro(Func),
+    Quote(Expr),               // unevaluated AST node made by `quote`
+    Cell(Rc<RefCell<Object>>), // variable of the VM shared with closures, never a value
     Null,
 }
 
@@ -15,9 +35,36 @@ impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Object::Int(v) => write!(f, "{}", v),
+            Object::BigInt(v) => write!(f, "{}", v),
+            Object::Float(v) => write!(f, "{:?}", v),
             Object::Bool(v) => write!(f, "{}", v),
+            Object::Str(v) => write!(f, "{}", v),
+            Object::Array(elements) => write!(
+                f,
+                "[{}]",
+                elements
+                    .iter()
+                    .map(|elem| format!("{}", elem))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Object::Hash(pairs) => write!(
+                f,
+                "{{{}}}",
+                pairs
+                    .iter()
+                    .map(|(key, val)| format!("{}: {}", key, val))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
             Object::Return(box v) => write!(f, "return {}", v),
             Object::Func(func) => func.fmt(f),
+            Object::Builtin(builtin) => write!(f, "builtin function: {}", builtin.name),
+            Object::CompiledFunction(func) => write!(f, "{}", func.repr),
+            Object::Closure(closure) => write!(f, "{}", closure.func.repr),
+            Object::Macro(mac) => write!(f, "{}", function_repr("macro", &mac.args, &mac.body)),
+            Object::Quote(expr) => write!(f, "QUOTE({})", expr),
+            Object::Cell(cell) => write!(f, "{}", cell.borrow()),
             Object::Null => write!(f, "null"),
         }
     }
@@ -25,6 +72,7 @@ impl fmt::Display for Object {
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Func {
+    pub name: Option<String>, // name of the `let` defining it, for error messages
     pub args: Vec<Ident>,
     pub body: BlockStmt,
     pub env: Env,
@@ -32,81 +80,372 @@ pub struct Func {
 
 impl fmt::Display for Func {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            r#"fn ({}) {{ {} }}"#,
-            self.args
-                .iter()
-                .map(|i| i.0.clone())
-                .collect::<Vec<String>>()
-                .join(", "),
-            self.body
-                .iter()
-                .map(|stmt| format!("{}", stmt))
-                .collect::<Vec<String>>()
-                .join("\n"),
-        )
+        write!(f, "{}", function_repr("fn", &self.args, &self.body))
+    }
+}
+
+/// how function values are printed, shared by the evaluator and the VM
+pub fn function_repr(keyword: &str, args: &[Ident], body: &[Stmt]) -> String {
+    format!(
+        r#"{} ({}) {{ {} }}"#,
+        keyword,
+        args.iter()
+            .map(|i| i.0.clone())
+            .collect::<Vec<String>>()
+            .join(", "),
+        body.iter()
+            .map(|stmt| format!("{}", stmt))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    )
+}
+
+/// function body compiled to bytecode (see `crate::compiler`)
+#[derive(Debug, PartialEq)]
+pub struct CompiledFunction {
+    pub name: Option<String>,
+    pub instructions: Instructions,
+    pub spans: SourceMap,
+    pub num_locals: usize,
+    pub num_params: usize,
+    pub repr: String,
+}
+
+/// compiled function with the free variables captured at its creation
+#[derive(Debug, PartialEq)]
+pub struct Closure {
+    pub func: Rc<CompiledFunction>,
+    pub free: Vec<Object>,
+}
+
+pub type BuiltinFunc = fn(Vec<Object>) -> EvalResult<Object>;
+
+/// function implemented in Rust (see `crate::builtins`)
+#[derive(Clone, Debug)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub func: BuiltinFunc,
+}
+
+// builtins are unique by name; comparing fn pointers is not reliable
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
     }
 }
 
 impl Object {
     pub fn get_type(&self) -> String {
         match self {
-            Object::Int(_) => "INT",
+            Object::Int(_) | Object::BigInt(_) => "INT",
+            Object::Float(_) => "FLOAT",
             Object::Bool(_) => "BOOLEAN",
+            Object::Str(_) => "STRING",
+            Object::Array(_) => "ARRAY",
+            Object::Hash(_) => "HASH",
             Object::Return(_) => "RETURN",
             Object::Func(_) => "FUNCTION",
+            Object::Builtin(_) => "BUILTIN",
+            Object::CompiledFunction(_) => "COMPILED_FUNCTION",
+            Object::Closure(_) => "FUNCTION",
+            Object::Macro(_) => "MACRO",
+            Object::Quote(_) => "QUOTE",
+            Object::Cell(_) => "CELL",
             Object::Null => "NULL",
         }
         .to_string()
     }
+
+    /// integer object, which is `Int` whenever the value fits in it
+    pub fn from_big_int(val: BigInt) -> Object {
+        match val.to_i64() {
+            Some(v) => Object::Int(v),
+            None => Object::BigInt(val),
+        }
+    }
+
+    /// returns `None` if this object cannot be used as a key of hash
+    pub fn to_hash_key(&self) -> Option<HashKey> {
+        match self {
+            Object::Int(v) => Some(HashKey::Int(*v)),
+            Object::BigInt(v) => Some(HashKey::BigInt(v.clone())),
+            Object::Bool(v) => Some(HashKey::Bool(*v)),
+            Object::Str(v) => Some(HashKey::Str(v.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// scalar objects which can be a key of `Object::Hash`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashKey {
+    Int(i64),
+    BigInt(BigInt),
+    Bool(bool),
+    Str(String),
+}
+
+impl fmt::Display for HashKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashKey::Int(v) => write!(f, "{}", v),
+            HashKey::BigInt(v) => write!(f, "{}", v),
+            HashKey::Bool(v) => write!(f, "{}", v),
+            HashKey::Str(v) => write!(f, "{}", v),
+        }
+    }
 }
 
 pub type EvalResult<T> = Result<T, EvalError>;
 
 #[derive(PartialEq, Debug)]
-pub struct EvalError(pub String);
+pub struct EvalError {
+    pub msg: String,
+    pub span: Option<Span>,
+}
+
+impl EvalError {
+    pub fn new(msg: String) -> Self {
+        EvalError { msg, span: None }
+    }
+
+    /// attach location unless the error already has the more precise one
+    pub fn or_span(self, span: Span) -> Self {
+        EvalError {
+            span: self.span.or(Some(span)),
+            ..self
+        }
+    }
+}
 
 impl fmt::Display for EvalError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match self.span {
+            Some(span) => write!(f, "{} at {}", self.msg, span),
+            None => write!(f, "{}", self.msg),
+        }
     }
 }
 
-#[derive(Clone, PartialEq, Debug)]
-pub struct Env {
+/// Lexical scope shared by reference.
+///
+/// Cloning an `Env` yields another handle to the same scope, so a closure
+/// capturing its defining scope sees bindings added to it later (e.g. its
+/// own name in `let f = fn() { f() }`).
+///
+/// A function stored in the scope it captures refers to it weakly, so that
+/// the two do not keep each other alive after a call returns. It is made
+/// strong again when read.
+#[derive(Clone)]
+pub struct Env(ScopeRef);
+
+#[derive(Clone)]
+enum ScopeRef {
+    Strong(Rc<RefCell<Scope>>),
+    Weak(Weak<RefCell<Scope>>), // only in values stored in the scope itself
+}
+
+struct Scope {
     store: HashMap<String, Object>,
-    source: Option<Box<Env>>,
+    source: Option<Env>,
+    calls: Rc<CallDepth>, // shared by all scopes wrapping the same root
 }
 
 impl Env {
     pub fn new() -> Self {
-        Env {
+        Self::with_max_call_depth(DEFAULT_MAX_CALL_DEPTH)
+    }
+
+    /// root scope allowing at most `max_call_depth` nested function calls
+    pub fn with_max_call_depth(max_call_depth: usize) -> Self {
+        Env::from_scope(Scope {
             store: HashMap::new(),
             source: None,
-        }
+            calls: Rc::new(CallDepth {
+                depth: Cell::new(0),
+                max: max_call_depth,
+            }),
+        })
     }
 
-    pub fn wrap(source_env: Env) -> Self {
-        Env {
+    pub fn wrap(source_env: &Env) -> Self {
+        Env::from_scope(Scope {
             store: HashMap::new(),
-            source: Some(Box::new(source_env)),
+            source: Some(source_env.clone()),
+            calls: source_env.scope().borrow().calls.clone(),
+        })
+    }
+
+    fn from_scope(scope: Scope) -> Self {
+        Env(ScopeRef::Strong(Rc::new(RefCell::new(scope))))
+    }
+
+    fn scope(&self) -> &Rc<RefCell<Scope>> {
+        match &self.0 {
+            ScopeRef::Strong(scope) => scope,
+            ScopeRef::Weak(_) => unreachable!("weak scope used before being read from its store"),
+        }
+    }
+
+    fn as_ptr(&self) -> *const RefCell<Scope> {
+        match &self.0 {
+            ScopeRef::Strong(scope) => Rc::as_ptr(scope),
+            ScopeRef::Weak(scope) => scope.as_ptr(),
+        }
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.scope().borrow().calls.max
+    }
+
+    /// count a function call until the returned guard is dropped.
+    /// `None` when the call would exceed the maximum depth
+    pub fn enter_call(&self) -> Option<CallGuard> {
+        let calls = self.scope().borrow().calls.clone();
+        if calls.depth.get() >= calls.max {
+            return None;
         }
+        calls.depth.set(calls.depth.get() + 1);
+        Some(CallGuard(calls))
     }
 
-    pub fn get(&self, key: String) -> Option<&Object> {
-        if let obj @ Some(_) = self.store.get(&key) {
-            obj
+    pub fn get(&self, key: &str) -> Option<Object> {
+        let scope = self.scope().borrow();
+        if let Some(obj) = scope.store.get(key) {
+            Some(Env::attach(obj.clone()))
+        } else if let Some(ref source) = scope.source {
+            source.get(key)
         } else {
-            if let Some(ref source) = self.source {
-                source.get(key)
-            } else {
-                None
+            None
+        }
+    }
+
+    pub fn insert(&self, key: String, val: Object) -> Option<Object> {
+        let val = self.detach(val);
+        self.scope().borrow_mut().store.insert(key, val)
+    }
+
+    /// replace the value of the nearest binding of `key` in this scope or the
+    /// enclosing ones. returns whether there was one
+    pub fn assign(&self, key: &str, val: Object) -> bool {
+        let source = {
+            let mut scope = self.scope().borrow_mut();
+            if let Some(slot) = scope.store.get_mut(key) {
+                *slot = self.detach(val);
+                return true;
+            }
+            scope.source.clone()
+        };
+        match source {
+            Some(source) => source.assign(key, val),
+            None => false,
+        }
+    }
+
+    /// names defined in this scope itself, sorted
+    pub fn bindings(&self) -> Vec<(String, Object)> {
+        let mut bindings: Vec<(String, Object)> = self
+            .scope()
+            .borrow()
+            .store
+            .iter()
+            .map(|(name, val)| (name.clone(), Env::attach(val.clone())))
+            .collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+
+    /// value to store in this scope, with functions capturing it referring to it weakly
+    fn detach(&self, val: Object) -> Object {
+        match val {
+            Object::Func(func) => Object::Func(self.detach_func(func)),
+            Object::Macro(mac) => Object::Macro(self.detach_func(mac)),
+            Object::Array(elements) => {
+                Object::Array(elements.into_iter().map(|elem| self.detach(elem)).collect())
             }
+            Object::Hash(pairs) => Object::Hash(
+                pairs
+                    .into_iter()
+                    .map(|(key, val)| (key, self.detach(val)))
+                    .collect(),
+            ),
+            val => val,
         }
     }
 
-    pub fn insert(&mut self, key: String, val: Object) -> Option<Object> {
-        self.store.insert(key, val)
+    fn detach_func(&self, func: Func) -> Func {
+        match &func.env.0 {
+            ScopeRef::Strong(scope) if func.env == *self => Func {
+                env: Env(ScopeRef::Weak(Rc::downgrade(scope))),
+                ..func
+            },
+            _ => func,
+        }
+    }
+
+    /// value read from a scope, with functions referring to it strongly again
+    fn attach(val: Object) -> Object {
+        match val {
+            Object::Func(func) => Object::Func(Env::attach_func(func)),
+            Object::Macro(mac) => Object::Macro(Env::attach_func(mac)),
+            Object::Array(elements) => {
+                Object::Array(elements.into_iter().map(Env::attach).collect())
+            }
+            Object::Hash(pairs) => Object::Hash(
+                pairs
+                    .into_iter()
+                    .map(|(key, val)| (key, Env::attach(val)))
+                    .collect(),
+            ),
+            val => val,
+        }
+    }
+
+    fn attach_func(func: Func) -> Func {
+        match &func.env.0 {
+            ScopeRef::Weak(scope) => Func {
+                // alive, since the function was read from it
+                env: Env(ScopeRef::Strong(scope.upgrade().expect("scope dropped"))),
+                ..func
+            },
+            ScopeRef::Strong(_) => func,
+        }
+    }
+}
+
+/// limit of nested function calls, checked before the native stack overflows
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 10000;
+
+struct CallDepth {
+    depth: Cell<usize>,
+    max: usize,
+}
+
+/// function call in progress (see `Env::enter_call`)
+pub struct CallGuard(Rc<CallDepth>);
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        self.0.depth.set(self.0.depth.get() - 1);
+    }
+}
+
+impl PartialEq for Env {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.as_ptr(), other.as_ptr())
+    }
+}
+
+// scopes may contain closures referring back to themselves, so only list the names
+impl fmt::Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scope = match &self.0 {
+            ScopeRef::Strong(scope) => scope.borrow(),
+            ScopeRef::Weak(_) => return write!(f, "Env(weak)"),
+        };
+        f.debug_struct("Env")
+            .field("store", &scope.store.keys().collect::<Vec<&String>>())
+            .field("source", &scope.source)
+            .finish()
     }
 }
@@ -0,0 +1,41 @@
+use super::*;
+use crate::evaluator::eval;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+fn run(input: &str, env: &Env) -> Object {
+    let mut l = Lexer::new(input);
+    let program = Parser::new(&mut l).parse_program();
+    eval(program, env).expect("evaluation failed")
+}
+
+#[test]
+fn test_function_in_its_scope_does_not_keep_it_alive() {
+    let env = Env::new();
+    run(
+        "let make = fn(n) { let g = fn() { n }; let h = fn() { g() }; h }; let f = make(1);",
+        &env,
+    );
+    let scope = match env.get("f") {
+        Some(Object::Func(func)) => func.env.scope().clone(),
+        obj => panic!("not a function: {:?}", obj),
+    };
+
+    // held by `f` and here, not by `g` and `h` stored in it
+    assert_eq!(Rc::strong_count(&scope), 2);
+    assert_eq!(run("f()", &env), Object::Int(1));
+    assert_eq!(Rc::strong_count(&scope), 2);
+
+    run("f = 0;", &env);
+    assert_eq!(Rc::strong_count(&scope), 1);
+}
+
+#[test]
+fn test_function_read_from_scope_is_strong() {
+    let env = Env::new();
+    run(
+        "let make = fn() { let g = fn() { 2 }; [g, {1: g}] }; let fs = make();",
+        &env,
+    );
+    assert_eq!(run("fs[0]() + fs[1][1]()", &env), Object::Int(4));
+}
@@ -0,0 +1,85 @@
+use crate::token::{Span, Token};
+use std::fmt;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ParseError {
+    /// token which cannot start an expression
+    UnexpectedToken {
+        got: Token<'static>,
+        span: Span,
+    },
+    ExpectedToken {
+        expected: Token<'static>,
+        got: Token<'static>,
+        span: Span,
+    },
+    ExpectedIdent {
+        got: Token<'static>,
+        span: Span,
+    },
+    /// block reached EOF before `}` (span points to the opening `{`)
+    UnterminatedBlock {
+        span: Span,
+    },
+    IllegalCharacter {
+        ch: String,
+        span: Span,
+    },
+    /// left side of `=` which is not a variable
+    InvalidAssignmentTarget {
+        span: Span,
+    },
+    /// block comment reached EOF before `*/` (span points to the opening `/*`)
+    UnterminatedComment {
+        span: Span,
+    },
+    /// string reached EOF before the closing `"` (span points to the opening one)
+    UnterminatedString {
+        span: Span,
+    },
+    /// `break` or `continue` not in a loop of the same function
+    OutsideLoop {
+        keyword: Token<'static>,
+        span: Span,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken { got, span } => {
+                write!(f, "unknown token in expression. got {:?} at {}", got, span)
+            }
+            ParseError::ExpectedToken {
+                expected,
+                got,
+                span,
+            } => write!(
+                f,
+                "expected next token to be {:?}, got {:?} instead at {}",
+                expected, got, span
+            ),
+            ParseError::ExpectedIdent { got, span } => {
+                write!(f, "expected identifier, got {:?} instead at {}", got, span)
+            }
+            ParseError::UnterminatedBlock { span } => {
+                write!(f, "unterminated block opened at {}", span)
+            }
+            ParseError::IllegalCharacter { ch, span } => {
+                write!(f, "illegal character '{}' at {}", ch.escape_debug(), span)
+            }
+            ParseError::InvalidAssignmentTarget { span } => {
+                write!(f, "invalid assignment target at {}", span)
+            }
+            ParseError::UnterminatedComment { span } => {
+                write!(f, "unterminated comment opened at {}", span)
+            }
+            ParseError::UnterminatedString { span } => {
+                write!(f, "unterminated string opened at {}", span)
+            }
+            ParseError::OutsideLoop { keyword, span } => {
+                write!(f, "'{}' outside loop at {}", keyword, span)
+            }
+        }
+    }
+}
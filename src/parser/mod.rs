@@ -1,6 +1,12 @@
-use crate::ast::{BlockStmt, Expr, Ident, Infix, Literal, Prefix, Program, Stmt};
+use crate::ast::{
+    BlockStmt, Expr, ExprKind, Ident, Infix, Literal, Prefix, Program, Stmt, StmtKind,
+};
 use crate::lexer::Lexer;
-use crate::token::Token;
+use crate::token::{Span, Token};
+
+mod error;
+
+pub use self::error::ParseError;
 
 #[cfg(test)]
 mod test;
@@ -8,36 +14,48 @@ mod test;
 #[cfg(test)]
 mod test_util;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialOrd, PartialEq)]
 enum Priority {
     LOWEST,
+    ASSIGN,
+    OR,
+    AND,
     EQUALS,
     LESSGREATER,
     SUM,
     PRODUCT,
     PREFIX,
+    POWER,
     CALL,
+    INDEX,
 }
 
 #[derive(Debug)]
-pub struct Parser<'a> {
-    lex: &'a mut Lexer,
-    pub errors: Vec<String>,
-
-    cur_token: Token,
-    peek_token: Token,
+pub struct Parser<'l, 'a> {
+    lex: &'l mut Lexer<'a>,
+    pub errors: Vec<ParseError>,
+
+    cur_token: Token<'a>,
+    peek_token: Token<'a>,
+    cur_span: Span,
+    peek_span: Span,
+    loop_depth: usize, // loops around the current token in the innermost function
 }
 
-impl<'a> Parser<'a> {
-    pub fn new(lex: &'a mut Lexer) -> Parser<'a> {
-        let cur_token = lex.next_token();
-        let peek_token = lex.next_token();
+impl<'l, 'a> Parser<'l, 'a> {
+    pub fn new(lex: &'l mut Lexer<'a>) -> Parser<'l, 'a> {
+        let (cur_token, cur_span) = lex.next_token();
+        let (peek_token, peek_span) = lex.next_token();
 
         Parser {
             lex,
             errors: Vec::new(),
             cur_token,
             peek_token,
+            cur_span,
+            peek_span,
+            loop_depth: 0,
         }
     }
 
@@ -46,53 +64,91 @@ impl<'a> Parser<'a> {
         let mut program = Program::new();
 
         while !self.cur_token_is(&Token::EOF) {
-            if let Some(statement) = self.parse_statement() {
-                program.statements.push(statement)
+            match self.parse_statement() {
+                Some(statement) => {
+                    program.statements.push(statement);
+                    self.next_token();
+                }
+                // stray `}` is left by `synchronize` as if it closed a block
+                None if self.cur_token_is(&Token::RBRACE) => self.next_token(),
+                None => {}
             }
-            self.next_token();
         }
         program
     }
 
     fn next_token(&mut self) {
-        self.cur_token = self.peek_token.clone();
-        self.peek_token = self.lex.next_token()
+        let (peek_token, peek_span) = self.lex.next_token();
+        self.cur_token = std::mem::replace(&mut self.peek_token, peek_token);
+        self.cur_span = std::mem::replace(&mut self.peek_span, peek_span);
     }
 
+    /// On failure the error is recorded and the parser is moved to the start of the next
+    /// statement (see `synchronize`); otherwise `cur_token` is the last token of the statement.
     fn parse_statement(&mut self) -> Option<Stmt> {
-        match self.cur_token {
+        let start = self.cur_span;
+        let kind = match self.cur_token {
             Token::LET => self.parse_let_statement(),
             Token::RETURN => self.parse_return_statement(),
+            Token::WHILE => self.parse_while_statement(),
+            Token::BREAK | Token::CONTINUE => self.parse_loop_control_statement(),
             _ => self.parse_expression_statement(),
+        };
+
+        match kind {
+            Some(kind) => Some(Stmt::new(kind, start.to(self.cur_span))),
+            None => {
+                self.synchronize();
+                None
+            }
         }
     }
 
-    fn parse_let_statement(&mut self) -> Option<Stmt> {
-        if let Token::IDENT(val) = self.peek_token.clone() {
+    /// Skip the rest of a broken statement and stop at the start of the next one
+    /// (after `;`, or at `let`/`return`/the `}` closing the enclosing block).
+    /// Brackets opened while skipping are skipped as a whole.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.cur_token {
+                Token::EOF => return,
+                Token::LET | Token::RETURN | Token::RBRACE if depth == 0 => return,
+                Token::SEMICOLON if depth == 0 => {
+                    self.next_token();
+                    return;
+                }
+                Token::LPAREN | Token::LBRACE | Token::LBRACKET => depth += 1,
+                Token::RPAREN | Token::RBRACKET if depth > 0 => depth -= 1,
+                Token::RBRACE => depth -= 1,
+                _ => {}
+            }
             self.next_token();
+        }
+    }
 
-            if !self.expect_peek(&Token::ASSIGN) {
-                return None;
-            }
+    fn parse_let_statement(&mut self) -> Option<StmtKind> {
+        self.next_token();
+        let ident = self.parse_ident()?;
 
-            self.next_token();
+        if !self.expect_peek(&Token::ASSIGN) {
+            return None;
+        }
 
-            let stmt = Stmt::Let(Ident(val), self.parse_expression(Priority::LOWEST)?);
+        self.next_token();
 
-            while self.peek_token_is(&Token::SEMICOLON) {
-                self.next_token()
-            }
+        let stmt = StmtKind::Let(ident, self.parse_expression(Priority::LOWEST)?);
 
-            Some(stmt)
-        } else {
-            None
+        while self.peek_token_is(&Token::SEMICOLON) {
+            self.next_token()
         }
+
+        Some(stmt)
     }
 
-    fn parse_return_statement(&mut self) -> Option<Stmt> {
+    fn parse_return_statement(&mut self) -> Option<StmtKind> {
         self.next_token();
 
-        let stmt = Stmt::Return(self.parse_expression(Priority::LOWEST)?);
+        let stmt = StmtKind::Return(self.parse_expression(Priority::LOWEST)?);
 
         while self.peek_token_is(&Token::SEMICOLON) {
             self.next_token();
@@ -101,9 +157,59 @@ impl<'a> Parser<'a> {
         Some(stmt)
     }
 
-    fn parse_expression_statement(&mut self) -> Option<Stmt> {
+    fn parse_while_statement(&mut self) -> Option<StmtKind> {
+        if !self.expect_peek(&Token::LPAREN) {
+            return None;
+        }
+
+        self.next_token();
+
+        let cond = self.parse_expression(Priority::LOWEST)?;
+
+        if !self.expect_peek(&Token::RPAREN) {
+            return None;
+        }
+
+        if !self.expect_peek(&Token::LBRACE) {
+            return None;
+        }
+
+        self.loop_depth += 1;
+        let body = self.parse_block_stmt();
+        self.loop_depth -= 1;
+
+        while self.peek_token_is(&Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(StmtKind::While(cond, body?))
+    }
+
+    /// `break` or `continue`, which must be in a loop of the same function
+    fn parse_loop_control_statement(&mut self) -> Option<StmtKind> {
+        let stmt = match self.cur_token {
+            Token::BREAK => StmtKind::Break,
+            _ => StmtKind::Continue,
+        };
+
+        if self.loop_depth == 0 {
+            self.errors.push(ParseError::OutsideLoop {
+                keyword: self.cur_token.clone().into_owned(),
+                span: self.cur_span,
+            });
+            return None;
+        }
+
+        while self.peek_token_is(&Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(stmt)
+    }
+
+    fn parse_expression_statement(&mut self) -> Option<StmtKind> {
         let expr = self.parse_expression(Priority::LOWEST)?;
-        let stmt = Stmt::Expr(expr);
+        let stmt = StmtKind::Expr(expr);
 
         if self.peek_token_is(&Token::SEMICOLON) {
             self.next_token()
@@ -113,10 +219,15 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_expression(&mut self, priority: Priority) -> Option<Expr> {
+        let start = self.cur_span;
+
         // prefix
-        let mut left = match self.cur_token {
+        let kind = match self.cur_token {
             Token::IDENT(_) => self.parse_identifier(),
             Token::INT(_) => self.parse_integer_literal(),
+            Token::BIGINT(_) => self.parse_big_integer_literal(),
+            Token::FLOAT(_) => self.parse_float_literal(),
+            Token::STRING(_) => self.parse_string_literal(),
             Token::TRUE | Token::FALSE => self.parse_bool_literal(),
             Token::PLUS => self.parse_prefix_expr(),
             Token::MINUS => self.parse_prefix_expr(),
@@ -124,106 +235,209 @@ impl<'a> Parser<'a> {
             Token::LPAREN => self.parse_grouped_expr(),
             Token::IF => self.parse_if_expr(),
             Token::FUNCTION => self.parse_function_literal(),
+            Token::MACRO => self.parse_macro_literal(),
+            Token::LBRACKET => self.parse_array_literal(),
+            // blocks only follow `if`/`fn`, so `{` in expression position is always a hash
+            Token::LBRACE => self.parse_hash_literal(),
+            Token::ILLEGAL(ref ch) => {
+                self.errors.push(ParseError::IllegalCharacter {
+                    ch: ch.clone(),
+                    span: self.cur_span,
+                });
+                None
+            }
+            Token::UNTERMINATEDCOMMENT => {
+                self.errors.push(ParseError::UnterminatedComment {
+                    span: self.cur_span,
+                });
+                None
+            }
+            Token::UNTERMINATEDSTRING => {
+                self.errors.push(ParseError::UnterminatedString {
+                    span: self.cur_span,
+                });
+                None
+            }
             _ => {
-                self.errors.push(format!(
-                    "unknown token in expression. got {:?}",
-                    self.cur_token
-                ));
+                self.unexpected_token_error();
                 None
             }
         }?;
+        let mut left = Expr::new(kind, start.to(self.cur_span));
 
         // not end of a statement and next token has more priority than current token
         while !self.peek_token_is(&Token::SEMICOLON) && priority < self.peek_priority() {
-            left = match self.peek_token {
+            let start = left.span;
+            let kind = match self.peek_token {
                 Token::PLUS
                 | Token::MINUS
                 | Token::SLASH
                 | Token::ASTERISK
+                | Token::PERCENT
+                | Token::POWER
                 | Token::EQ
                 | Token::NOTEQ
+                | Token::AND
+                | Token::OR
                 | Token::LT
-                | Token::GT => {
+                | Token::GT
+                | Token::LTEQ
+                | Token::GTEQ => {
                     self.next_token();
                     self.parse_infix_expr(left)?
                 }
+                // `=` after `let` is part of the statement, so here it is always an assignment
+                Token::ASSIGN
+                | Token::PLUSASSIGN
+                | Token::MINUSASSIGN
+                | Token::ASTERISKASSIGN
+                | Token::SLASHASSIGN => {
+                    self.next_token();
+                    self.parse_assign_expr(left)?
+                }
                 Token::LPAREN => {
                     self.next_token();
                     self.parse_call_expr(left)?
                 }
+                Token::LBRACKET => {
+                    self.next_token();
+                    self.parse_index_expr(left)?
+                }
                 _ => return Some(left),
             };
+            left = Expr::new(kind, start.to(self.cur_span));
         }
         Some(left)
     }
 
-    fn parse_identifier(&self) -> Option<Expr> {
+    fn parse_identifier(&self) -> Option<ExprKind> {
         if let Token::IDENT(val) = &self.cur_token {
-            Some(Expr::Ident(Ident(val.clone())))
+            Some(ExprKind::Ident(Ident(val.to_string())))
         } else {
             None
         }
     }
 
-    fn parse_integer_literal(&mut self) -> Option<Expr> {
+    fn parse_integer_literal(&mut self) -> Option<ExprKind> {
         if let Token::INT(val) = self.cur_token {
-            Some(Expr::Literal(Literal::Int(val)))
+            Some(ExprKind::Literal(Literal::Int(val)))
+        } else {
+            None
+        }
+    }
+
+    fn parse_big_integer_literal(&mut self) -> Option<ExprKind> {
+        if let Token::BIGINT(val) = &self.cur_token {
+            Some(ExprKind::Literal(Literal::BigInt(val.clone())))
         } else {
             None
         }
     }
 
-    fn parse_bool_literal(&mut self) -> Option<Expr> {
+    fn parse_float_literal(&mut self) -> Option<ExprKind> {
+        if let Token::FLOAT(val) = self.cur_token {
+            Some(ExprKind::Literal(Literal::Float(val)))
+        } else {
+            None
+        }
+    }
+
+    fn parse_string_literal(&mut self) -> Option<ExprKind> {
+        if let Token::STRING(val) = &self.cur_token {
+            Some(ExprKind::Literal(Literal::Str(val.to_string())))
+        } else {
+            None
+        }
+    }
+
+    fn parse_bool_literal(&mut self) -> Option<ExprKind> {
         match self.cur_token {
-            Token::TRUE => Some(Expr::Literal(Literal::Bool(true))),
-            Token::FALSE => Some(Expr::Literal(Literal::Bool(false))),
+            Token::TRUE => Some(ExprKind::Literal(Literal::Bool(true))),
+            Token::FALSE => Some(ExprKind::Literal(Literal::Bool(false))),
             _ => None,
         }
     }
 
-    fn parse_prefix_expr(&mut self) -> Option<Expr> {
+    fn parse_prefix_expr(&mut self) -> Option<ExprKind> {
         let cur_token = self.cur_token.clone(); // PLUS
 
         self.next_token();
 
         let expr = self.parse_expression(Priority::PREFIX)?;
         match Prefix::from_token(&cur_token) {
-            Ok(prefix) => Some(Expr::Prefix(prefix, Box::new(expr))),
-            Err(err) => {
-                self.errors.push(err);
+            Ok(prefix) => Some(ExprKind::Prefix(prefix, Box::new(expr))),
+            Err(_) => {
+                self.unexpected_token_error();
                 None
             }
         }
     }
 
-    fn parse_infix_expr(&mut self, left: Expr) -> Option<Expr> {
+    fn parse_infix_expr(&mut self, left: Expr) -> Option<ExprKind> {
         let cur_token = self.cur_token.clone(); // PLUS
-        let priority = self.cur_priority(); // SUM
+        let priority = match cur_token {
+            // right associative. parsing the right operand one level below `POWER`
+            // lets the following `**` bind first
+            Token::POWER => Priority::PREFIX,
+            _ => self.cur_priority(), // SUM
+        };
         self.next_token();
 
         let right = self.parse_expression(priority)?;
         match Infix::from_token(&cur_token) {
-            Ok(infix) => Some(Expr::Infix(Box::new(left), infix, Box::new(right))),
-            Err(err) => {
-                self.errors.push(err);
+            Ok(infix) => Some(ExprKind::Infix(Box::new(left), infix, Box::new(right))),
+            Err(_) => {
+                self.unexpected_token_error();
                 None
             }
         }
     }
 
-    fn parse_grouped_expr(&mut self) -> Option<Expr> {
+    /// `x = value`. compound assignment like `x += value` becomes `x = x + value`
+    fn parse_assign_expr(&mut self, target: Expr) -> Option<ExprKind> {
+        let ident = match target.kind {
+            ExprKind::Ident(ident) => ident,
+            _ => {
+                self.errors
+                    .push(ParseError::InvalidAssignmentTarget { span: target.span });
+                return None;
+            }
+        };
+        let operator = match self.cur_token {
+            Token::PLUSASSIGN => Some(Infix::Plus),
+            Token::MINUSASSIGN => Some(Infix::Minus),
+            Token::ASTERISKASSIGN => Some(Infix::Multiply),
+            Token::SLASHASSIGN => Some(Infix::Divide),
+            _ => None,
+        };
+        self.next_token();
+
+        // right associative, so `a = b = 1` assigns to `b` first
+        let mut value = self.parse_expression(Priority::LOWEST)?;
+        if let Some(infix) = operator {
+            let span = target.span.to(value.span);
+            let current = Expr::new(ExprKind::Ident(ident.clone()), target.span);
+            value = Expr::new(
+                ExprKind::Infix(Box::new(current), infix, Box::new(value)),
+                span,
+            );
+        }
+        Some(ExprKind::Assign(ident, Box::new(value)))
+    }
+
+    fn parse_grouped_expr(&mut self) -> Option<ExprKind> {
         self.next_token();
 
-        let expr = self.parse_expression(Priority::LOWEST);
+        let expr = self.parse_expression(Priority::LOWEST)?;
 
         if !self.expect_peek(&Token::RPAREN) {
             None
         } else {
-            expr
+            Some(expr.kind)
         }
     }
 
-    fn parse_if_expr(&mut self) -> Option<Expr> {
+    fn parse_if_expr(&mut self) -> Option<ExprKind> {
         if !self.expect_peek(&Token::LPAREN) {
             return None;
         }
@@ -240,7 +454,7 @@ impl<'a> Parser<'a> {
             return None;
         }
 
-        let cons = Stmt::Block(self.parse_block_stmt());
+        let cons = self.parse_block()?;
 
         let alter = if self.peek_token_is(&Token::ELSE) {
             self.next_token();
@@ -248,94 +462,184 @@ impl<'a> Parser<'a> {
             if !self.expect_peek(&Token::LBRACE) {
                 return None;
             }
-            Some(Box::new(Stmt::Block(self.parse_block_stmt())))
+            Some(Box::new(self.parse_block()?))
         } else {
             None
         };
 
-        Some(Expr::If(Box::new(cond), Box::new(cons), alter))
+        Some(ExprKind::If(Box::new(cond), Box::new(cons), alter))
+    }
+
+    /// parse block as a statement, e.g. branches of `if`
+    fn parse_block(&mut self) -> Option<Stmt> {
+        let start = self.cur_span;
+        let stmts = self.parse_block_stmt()?;
+        Some(Stmt::new(StmtKind::Block(stmts), start.to(self.cur_span)))
     }
 
-    fn parse_block_stmt(&mut self) -> BlockStmt {
+    fn parse_block_stmt(&mut self) -> Option<BlockStmt> {
+        let start = self.cur_span;
         self.next_token();
 
         let mut stmts = Vec::new();
-        while !self.cur_token_is(&Token::RBRACE) && !self.cur_token_is(&Token::EOF) {
+        while !self.cur_token_is(&Token::RBRACE) {
+            if self.cur_token_is(&Token::EOF) {
+                self.errors
+                    .push(ParseError::UnterminatedBlock { span: start });
+                return None;
+            }
             if let Some(stmt) = self.parse_statement() {
                 stmts.push(stmt);
+                self.next_token();
             }
-            self.next_token();
         }
 
-        stmts
+        Some(stmts)
+    }
+
+    fn parse_function_literal(&mut self) -> Option<ExprKind> {
+        if !self.expect_peek(&Token::LPAREN) {
+            return None;
+        }
+        let params = self.parse_function_params()?;
+
+        if !self.expect_peek(&Token::LBRACE) {
+            None
+        } else {
+            Some(ExprKind::Function(params, self.parse_function_body()?))
+        }
     }
 
-    fn parse_function_literal(&mut self) -> Option<Expr> {
+    fn parse_macro_literal(&mut self) -> Option<ExprKind> {
         if !self.expect_peek(&Token::LPAREN) {
             return None;
         }
-        let params = self.parse_function_params();
+        let params = self.parse_function_params()?;
 
         if !self.expect_peek(&Token::LBRACE) {
             None
         } else {
-            Some(Expr::Function(params, self.parse_block_stmt()))
+            Some(ExprKind::Macro(params, self.parse_function_body()?))
         }
     }
 
-    fn parse_function_params(&mut self) -> Vec<Ident> {
+    /// parse body of a function or macro, which is not in the loops around it
+    fn parse_function_body(&mut self) -> Option<BlockStmt> {
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.parse_block_stmt();
+        self.loop_depth = loop_depth;
+        body
+    }
+
+    fn parse_function_params(&mut self) -> Option<Vec<Ident>> {
         let mut idents = Vec::new();
 
         if self.peek_token_is(&Token::RPAREN) {
             self.next_token();
-            return idents;
+            return Some(idents);
         }
 
         self.next_token();
-
-        let ident = Ident(format!("{}", self.cur_token));
-        idents.push(ident);
+        idents.push(self.parse_ident()?);
 
         while self.peek_token_is(&Token::COMMA) {
             self.next_token(); // skip comma
             self.next_token();
-            let ident = Ident(format!("{}", self.cur_token));
-            idents.push(ident)
+            idents.push(self.parse_ident()?);
         }
 
         if !self.expect_peek(&Token::RPAREN) {
-            return vec![];
+            return None;
         }
 
-        idents
+        Some(idents)
+    }
+
+    /// identifier at binding position (`let`, function params)
+    fn parse_ident(&mut self) -> Option<Ident> {
+        if let Token::IDENT(val) = &self.cur_token {
+            Some(Ident(val.to_string()))
+        } else {
+            self.errors.push(ParseError::ExpectedIdent {
+                got: self.cur_token.clone().into_owned(),
+                span: self.cur_span,
+            });
+            None
+        }
     }
 
-    fn parse_call_expr(&mut self, func: Expr) -> Option<Expr> {
-        Some(Expr::Call(Box::new(func), self.parse_call_args()))
+    fn parse_call_expr(&mut self, func: Expr) -> Option<ExprKind> {
+        let args = self.parse_expr_list(&Token::RPAREN)?;
+        Some(ExprKind::Call(Box::new(func), args))
     }
 
-    fn parse_call_args(&mut self) -> Vec<Expr> {
-        let mut args = Vec::new();
+    fn parse_array_literal(&mut self) -> Option<ExprKind> {
+        let elements = self.parse_expr_list(&Token::RBRACKET)?;
+        Some(ExprKind::Array(elements))
+    }
 
-        if self.peek_token_is(&Token::RPAREN) {
+    fn parse_hash_literal(&mut self) -> Option<ExprKind> {
+        let mut pairs = Vec::new();
+
+        while !self.peek_token_is(&Token::RBRACE) {
+            self.next_token();
+            let key = self.parse_expression(Priority::LOWEST)?;
+
+            if !self.expect_peek(&Token::COLON) {
+                return None;
+            }
+
+            self.next_token();
+            let val = self.parse_expression(Priority::LOWEST)?;
+            pairs.push((key, val));
+
+            if !self.peek_token_is(&Token::RBRACE) && !self.expect_peek(&Token::COMMA) {
+                return None;
+            }
+        }
+
+        if !self.expect_peek(&Token::RBRACE) {
+            return None;
+        }
+
+        Some(ExprKind::Hash(pairs))
+    }
+
+    fn parse_index_expr(&mut self, left: Expr) -> Option<ExprKind> {
+        self.next_token();
+
+        let index = self.parse_expression(Priority::LOWEST)?;
+
+        if !self.expect_peek(&Token::RBRACKET) {
+            return None;
+        }
+
+        Some(ExprKind::Index(Box::new(left), Box::new(index)))
+    }
+
+    /// parse comma separated expressions until `end` token (e.g. call args, array elements)
+    fn parse_expr_list(&mut self, end: &Token) -> Option<Vec<Expr>> {
+        let mut list = Vec::new();
+
+        if self.peek_token_is(end) {
             self.next_token();
-            return args;
+            return Some(list);
         }
 
         self.next_token();
-        args.push(self.parse_expression(Priority::LOWEST).expect("args"));
+        list.push(self.parse_expression(Priority::LOWEST)?);
 
         while self.peek_token_is(&Token::COMMA) {
             self.next_token();
             self.next_token();
-            args.push(self.parse_expression(Priority::LOWEST).expect("args"))
+            list.push(self.parse_expression(Priority::LOWEST)?)
         }
 
-        if !self.expect_peek(&Token::RPAREN) {
-            return vec![];
+        if !self.expect_peek(end) {
+            return None;
         }
 
-        args
+        Some(list)
     }
 
     fn cur_token_is(&self, tok: &Token) -> bool {
@@ -357,23 +661,43 @@ impl<'a> Parser<'a> {
     }
 
     fn peek_error(&mut self, tok: &Token) {
-        self.errors.push(format!(
-            "expected next token to be {:?}, got {:?} instead",
-            tok, self.peek_token
-        ))
+        self.errors.push(ParseError::ExpectedToken {
+            expected: tok.clone().into_owned(),
+            got: self.peek_token.clone().into_owned(),
+            span: self.peek_span,
+        })
+    }
+
+    fn unexpected_token_error(&mut self) {
+        self.errors.push(ParseError::UnexpectedToken {
+            got: self.cur_token.clone().into_owned(),
+            span: self.cur_span,
+        })
     }
 
     fn get_priority(tok: &Token) -> Priority {
         match tok {
+            Token::ASSIGN
+            | Token::PLUSASSIGN
+            | Token::MINUSASSIGN
+            | Token::ASTERISKASSIGN
+            | Token::SLASHASSIGN => Priority::ASSIGN,
+            Token::OR => Priority::OR,
+            Token::AND => Priority::AND,
             Token::EQ => Priority::EQUALS,
             Token::NOTEQ => Priority::EQUALS,
             Token::LT => Priority::LESSGREATER,
             Token::GT => Priority::LESSGREATER,
+            Token::LTEQ => Priority::LESSGREATER,
+            Token::GTEQ => Priority::LESSGREATER,
             Token::PLUS => Priority::SUM,
             Token::MINUS => Priority::SUM,
             Token::SLASH => Priority::PRODUCT,
             Token::ASTERISK => Priority::PRODUCT,
+            Token::PERCENT => Priority::PRODUCT,
+            Token::POWER => Priority::POWER,
             Token::LPAREN => Priority::CALL,
+            Token::LBRACKET => Priority::INDEX,
             _ => Priority::LOWEST,
         }
     }
@@ -1,12 +1,12 @@
 use super::test_util as util;
 use super::*;
-use crate::ast::{Expr, Ident, Infix, Literal, Prefix, Stmt};
+use crate::ast::{Expr, ExprKind, Ident, Infix, Literal, Prefix, Stmt, StmtKind};
 use crate::lexer::Lexer;
 
 fn check_parser_errors(p: Parser) {
     let errors = p.errors;
 
-    if errors.len() == 0 {
+    if errors.is_empty() {
         return;
     }
 
@@ -32,26 +32,26 @@ fn check_stmt_len(program: &Program, len: usize) {
 #[test]
 fn test_let_stmts() {
     let test_cases = vec![
-        ("let x = 5;", "x", Expr::Literal(Literal::Int(5))),
-        ("let y = true;", "y", Expr::Literal(Literal::Bool(true))),
+        ("let x = 5;", "x", ExprKind::Literal(Literal::Int(5))),
+        ("let y = true;", "y", ExprKind::Literal(Literal::Bool(true))),
         (
             "let foobar = y;",
             "foobar",
-            Expr::Ident(Ident("y".to_string())),
+            ExprKind::Ident(Ident("y".to_string())),
         ),
     ];
 
     for (input, expect_ident, expect_expr) in test_cases {
-        let mut lex = Lexer::new(input.to_string()).unwrap();
+        let mut lex = Lexer::new(input);
         let mut p = Parser::new(&mut lex);
 
         let program = p.parse_program();
         check_parser_errors(p);
         check_stmt_len(&program, 1);
+        util::check_let_stmt(&program.statements[0], expect_ident);
 
-        if let Stmt::Let(ident, expr) = &program.statements[0] {
-            assert_eq!(format!("{}", ident), expect_ident);
-            assert_eq!(*expr, expect_expr);
+        if let StmtKind::Let(_, expr) = &program.statements[0].kind {
+            assert_eq!(expr.kind, expect_expr);
         } else {
             unreachable!()
         }
@@ -61,30 +61,33 @@ fn test_let_stmts() {
 #[test]
 fn test_return_stmt() {
     let test_cases = vec![
-        ("return 5;", Expr::Literal(Literal::Int(5))),
-        ("return true;", Expr::Literal(Literal::Bool(true))),
-        ("return foobar;", Expr::Ident(Ident("foobar".to_string()))),
+        ("return 5;", ExprKind::Literal(Literal::Int(5))),
+        ("return true;", ExprKind::Literal(Literal::Bool(true))),
+        (
+            "return foobar;",
+            ExprKind::Ident(Ident("foobar".to_string())),
+        ),
     ];
 
     for (input, expect_expr) in test_cases {
-        let mut lex = Lexer::new(input.to_string()).unwrap();
+        let mut lex = Lexer::new(input);
         let mut psr = Parser::new(&mut lex);
 
         let program = psr.parse_program();
         check_parser_errors(psr);
         check_stmt_len(&program, 1);
 
-        if let Stmt::Return(expr) = &program.statements[0] {
-            assert_eq!(*expr, expect_expr);
+        if let StmtKind::Return(expr) = &program.statements[0].kind {
+            assert_eq!(expr.kind, expect_expr);
         }
     }
 }
 
 #[test]
 fn test_ident_expr() {
-    let input = r#"foobar;"#.to_string();
+    let input = r#"foobar;"#;
 
-    let mut lex = Lexer::new(input).unwrap();
+    let mut lex = Lexer::new(input);
     let mut p = Parser::new(&mut lex);
 
     let program = p.parse_program();
@@ -96,9 +99,9 @@ fn test_ident_expr() {
 
 #[test]
 fn test_integer_literal_expr() {
-    let input = r#"5;"#.to_string();
+    let input = r#"5;"#;
 
-    let mut lex = Lexer::new(input).unwrap();
+    let mut lex = Lexer::new(input);
     let mut p = Parser::new(&mut lex);
 
     let program = p.parse_program();
@@ -108,12 +111,43 @@ fn test_integer_literal_expr() {
     util::check_stmt(&program.statements[0], 5)
 }
 
+#[test]
+fn test_float_literal_expr() {
+    let input = r#"2.5;"#;
+
+    let mut lex = Lexer::new(input);
+    let mut p = Parser::new(&mut lex);
+
+    let program = p.parse_program();
+    check_parser_errors(p);
+    check_stmt_len(&program, 1);
+
+    util::check_stmt(&program.statements[0], 2.5)
+}
+
+#[test]
+fn test_string_literal_expr() {
+    let input = r#""hello world";"#;
+
+    let mut lex = Lexer::new(input);
+    let mut p = Parser::new(&mut lex);
+
+    let program = p.parse_program();
+    check_parser_errors(p);
+    check_stmt_len(&program, 1);
+
+    assert_eq!(
+        *util::expr_kind(&program.statements[0]),
+        ExprKind::Literal(Literal::Str("hello world".to_string()))
+    );
+}
+
 #[test]
 fn test_boolean_literal_expr() {
     let test_cases = vec![("true;", true), ("false;", false)];
 
     for (input, expect) in test_cases {
-        let mut lex = Lexer::new(input.to_string()).unwrap();
+        let mut lex = Lexer::new(input);
         let mut p = Parser::new(&mut lex);
         let program = p.parse_program();
 
@@ -128,14 +162,19 @@ fn test_parse_prefix_expr() {
     let prefix_tests = vec![("!5;", Prefix::Not, 5), ("-15;", Prefix::Minus, 15)];
 
     for (input, expect_prefix, expect_val) in prefix_tests {
-        let mut l = Lexer::new(input.to_string()).unwrap();
+        let mut l = Lexer::new(input);
         let mut p = Parser::new(&mut l);
         let program = p.parse_program();
         check_parser_errors(p);
         check_stmt_len(&program, 1);
 
-        if let Stmt::Expr(Expr::Prefix(prefix, box Expr::Literal(Literal::Int(val)))) =
-            &program.statements[0]
+        if let ExprKind::Prefix(
+            prefix,
+            box Expr {
+                kind: ExprKind::Literal(Literal::Int(val)),
+                ..
+            },
+        ) = util::expr_kind(&program.statements[0])
         {
             assert_eq!(*prefix, expect_prefix);
             assert_eq!(*val, expect_val);
@@ -159,7 +198,7 @@ fn test_parse_infix_expr() {
     ];
 
     for (input, expect_left, expect_infix, expect_right) in infix_tests {
-        let mut l = Lexer::new(input.to_string()).unwrap();
+        let mut l = Lexer::new(input);
         let mut p = Parser::new(&mut l);
         let program = p.parse_program();
         check_parser_errors(p);
@@ -188,6 +227,18 @@ fn test_operator_precedence_parsing() {
         ("3 + 4; -5 * 5", "(3 + 4)((-5) * 5)"),
         ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
         ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4))"),
+        ("a || b && c == d", "(a || (b && (c == d)))"),
+        ("a && b || c && d", "((a && b) || (c && d))"),
+        ("!a && b", "((!a) && b)"),
+        ("a <= b == c >= d", "((a <= b) == (c >= d))"),
+        ("a % b + c", "((a % b) + c)"),
+        ("2 ** 3 ** 2", "(2 ** (3 ** 2))"),
+        ("x = y = a || b", "(x = (y = (a || b)))"),
+        ("x += 2 * y", "(x = (x + (2 * y)))"),
+        ("a * b ** c", "(a * (b ** c))"),
+        ("-a ** b", "(-(a ** b))"),
+        ("a ** -b", "(a ** (-b))"),
+        ("a ** b[0]", "(a ** (b[0]))"),
         (
             "3 + 4 * 5 == 3 * 1 + 4 * 5",
             "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
@@ -206,10 +257,18 @@ fn test_operator_precedence_parsing() {
             "add(a + b + c * d / f + g)",
             "add((((a + b) + ((c * d) / f)) + g))",
         ),
+        (
+            "a * [1, 2, 3, 4][b * c] * d",
+            "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+        ),
+        (
+            "add(a * b[2], b[1], 2 * [1, 2][1])",
+            "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+        ),
     ];
 
     for (input, expect) in test_cases {
-        let mut l = Lexer::new(input.to_string()).unwrap();
+        let mut l = Lexer::new(input);
         let mut p = Parser::new(&mut l);
         let program = p.parse_program();
         check_parser_errors(p);
@@ -220,16 +279,22 @@ fn test_operator_precedence_parsing() {
 
 #[test]
 fn test_if_expr() {
-    let input = r#"if (x < y) { x }"#.to_string();
+    let input = r#"if (x < y) { x }"#;
 
-    let mut l = Lexer::new(input).unwrap();
+    let mut l = Lexer::new(input);
     let mut p = Parser::new(&mut l);
     let program = p.parse_program();
     check_parser_errors(p);
     check_stmt_len(&program, 1);
 
-    if let Stmt::Expr(Expr::If(box cond, box Stmt::Block(cons_stmts), None)) =
-        &program.statements[0]
+    if let ExprKind::If(
+        box cond,
+        box Stmt {
+            kind: StmtKind::Block(cons_stmts),
+            ..
+        },
+        None,
+    ) = util::expr_kind(&program.statements[0])
     {
         util::check_infix_expr(cond, "x", Infix::LessThan, "y");
         util::check_stmt(&cons_stmts[0], "x");
@@ -240,18 +305,24 @@ fn test_if_expr() {
 
 #[test]
 fn test_if_else_expr() {
-    let input = r#"if (x < y) { x } else { y }"#.to_string();
-    let mut l = Lexer::new(input).unwrap();
+    let input = r#"if (x < y) { x } else { y }"#;
+    let mut l = Lexer::new(input);
     let mut p = Parser::new(&mut l);
     let program = p.parse_program();
     check_parser_errors(p);
     check_stmt_len(&program, 1);
 
-    if let Stmt::Expr(Expr::If(
+    if let ExprKind::If(
         box cond,
-        box Stmt::Block(cons_stmts),
-        Some(box Stmt::Block(alter_stmts)),
-    )) = &program.statements[0]
+        box Stmt {
+            kind: StmtKind::Block(cons_stmts),
+            ..
+        },
+        Some(box Stmt {
+            kind: StmtKind::Block(alter_stmts),
+            ..
+        }),
+    ) = util::expr_kind(&program.statements[0])
     {
         util::check_infix_expr(cond, "x", Infix::LessThan, "y");
         util::check_stmt(&cons_stmts[0], "x");
@@ -261,16 +332,46 @@ fn test_if_else_expr() {
     }
 }
 
+#[test]
+fn test_while_stmt() {
+    let input = r#"while (x < y) { if (x) { break; } continue; };"#;
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+    check_parser_errors(p);
+    check_stmt_len(&program, 1);
+
+    if let StmtKind::While(cond, body) = &program.statements[0].kind {
+        util::check_infix_expr(cond, "x", Infix::LessThan, "y");
+        assert_eq!(body.len(), 2);
+        assert_eq!(body[1].kind, StmtKind::Continue);
+    } else {
+        unreachable!()
+    }
+    assert_eq!(
+        format!("{}", program),
+        "while ((x < y)) { if x break; continue; }"
+    );
+
+    // the printed loop parses to the same one
+    let input = "while (x) { let y = x - 1; x = y; break }";
+    let program = Parser::new(&mut Lexer::new(input)).parse_program();
+    let printed = format!("{}", program);
+    assert_eq!(printed, "while (x) { let y = (x - 1); (x = y); break; }");
+    let reparsed = Parser::new(&mut Lexer::new(&printed)).parse_program();
+    assert_eq!(format!("{}", reparsed), printed);
+}
+
 #[test]
 fn test_function_literal_parse() {
-    let input = r#"fn (x, y) { x + y; }"#.to_string();
-    let mut l = Lexer::new(input).unwrap();
+    let input = r#"fn (x, y) { x + y; }"#;
+    let mut l = Lexer::new(input);
     let mut p = Parser::new(&mut l);
     let program = p.parse_program();
     check_parser_errors(p);
     check_stmt_len(&program, 1);
 
-    if let Stmt::Expr(Expr::Function(params, stmts)) = &program.statements[0] {
+    if let ExprKind::Function(params, stmts) = util::expr_kind(&program.statements[0]) {
         assert_eq!(params.len(), 2);
         assert_eq!(params[0].0, "x");
         assert_eq!(params[1].0, "y");
@@ -291,12 +392,12 @@ fn test_function_param_parse() {
     ];
 
     for (input, expect) in test_cases {
-        let mut l = Lexer::new(input.to_string()).unwrap();
+        let mut l = Lexer::new(input);
         let mut p = Parser::new(&mut l);
         let program = p.parse_program();
         check_parser_errors(p);
 
-        if let Stmt::Expr(Expr::Function(params, _)) = &program.statements[0] {
+        if let ExprKind::Function(params, _) = util::expr_kind(&program.statements[0]) {
             assert_eq!(params.len(), expect.len());
 
             for (Ident(actual_param), expect_param) in params.iter().zip(expect.iter()) {
@@ -308,16 +409,37 @@ fn test_function_param_parse() {
     }
 }
 
+#[test]
+fn test_macro_literal_parse() {
+    let input = "macro(x, y) { x + y; }";
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+    check_parser_errors(p);
+    check_stmt_len(&program, 1);
+
+    if let ExprKind::Macro(params, stmts) = util::expr_kind(&program.statements[0]) {
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].0, "x");
+        assert_eq!(params[1].0, "y");
+
+        assert_eq!(stmts.len(), 1);
+        util::check_infix_stmt(&stmts[0], "x", Infix::Plus, "y");
+    } else {
+        unreachable!()
+    }
+}
+
 #[test]
 fn test_call_expr_parse() {
-    let input = "add(1, 2 * 3, 4 + 5)".to_string();
-    let mut l = Lexer::new(input).unwrap();
+    let input = "add(1, 2 * 3, 4 + 5)";
+    let mut l = Lexer::new(input);
     let mut p = Parser::new(&mut l);
     let program = p.parse_program();
     check_parser_errors(p);
     check_stmt_len(&program, 1);
 
-    if let Stmt::Expr(Expr::Call(box func, params)) = &program.statements[0] {
+    if let ExprKind::Call(box func, params) = util::expr_kind(&program.statements[0]) {
         util::check_expr(func, "add");
         assert_eq!(params.len(), 3);
         util::check_expr(&params[0], 1);
@@ -341,12 +463,12 @@ fn test_call_expr_param_parse() {
     ];
 
     for (input, expect_ident, expect_args) in test_cases {
-        let mut l = Lexer::new(input.to_string()).unwrap();
+        let mut l = Lexer::new(input);
         let mut p = Parser::new(&mut l);
         let program = p.parse_program();
         check_parser_errors(p);
 
-        if let Stmt::Expr(Expr::Call(box func, params)) = &program.statements[0] {
+        if let ExprKind::Call(box func, params) = util::expr_kind(&program.statements[0]) {
             util::check_expr(func, expect_ident);
             assert_eq!(params.len(), expect_args.len());
             for (actual, expect) in params.iter().zip(expect_args.iter()) {
@@ -357,3 +479,169 @@ fn test_call_expr_param_parse() {
         }
     }
 }
+
+#[test]
+fn test_array_literal_parse() {
+    let input = "[1, 2 * 2, 3 + 3]";
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+    check_parser_errors(p);
+    check_stmt_len(&program, 1);
+
+    if let ExprKind::Array(elements) = util::expr_kind(&program.statements[0]) {
+        assert_eq!(elements.len(), 3);
+        util::check_expr(&elements[0], 1);
+        util::check_infix_expr(&elements[1], 2, Infix::Multiply, 2);
+        util::check_infix_expr(&elements[2], 3, Infix::Plus, 3);
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_index_expr_parse() {
+    let input = "myArray[1 + 1]";
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+    check_parser_errors(p);
+    check_stmt_len(&program, 1);
+
+    if let ExprKind::Index(box left, box index) = util::expr_kind(&program.statements[0]) {
+        util::check_expr(left, "myArray");
+        util::check_infix_expr(index, 1, Infix::Plus, 1);
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_hash_literal_parse() {
+    let test_cases = vec![
+        ("{}", "{}"),
+        (r#"{"one": 1, "two": 2}"#, r#"{"one": 1, "two": 2}"#),
+        (r#"{1: true, false: "no"}"#, r#"{1: true, false: "no"}"#),
+        (
+            r#"{"one": 0 + 1, "two": 10 - 8}"#,
+            r#"{"one": (0 + 1), "two": (10 - 8)}"#,
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+        let program = p.parse_program();
+        check_parser_errors(p);
+        check_stmt_len(&program, 1);
+
+        if let expr @ ExprKind::Hash(_) = util::expr_kind(&program.statements[0]) {
+            assert_eq!(format!("{}", expr), expect);
+        } else {
+            unreachable!()
+        }
+    }
+}
+
+#[test]
+fn test_parse_error_location() {
+    let test_cases = vec![
+        (
+            "let x 5;",
+            "expected next token to be ASSIGN, got INT(5) instead at 1:7",
+        ),
+        (
+            "let x = 1;\nif (x { x }",
+            "expected next token to be RPAREN, got LBRACE instead at 2:7",
+        ),
+        ("1 + ;", "unknown token in expression. got SEMICOLON at 1:5"),
+        ("let x = 1 → 2;", "illegal character '→' at 1:11"),
+        ("let x = 5 @ 3; puts(x)", "illegal character '@' at 1:11"),
+        ("a & b", "illegal character '&' at 1:3"),
+        (
+            "let x = 1;\n/* a /* b */",
+            "unterminated comment opened at 2:1",
+        ),
+        ("add(1, /* 2)", "unterminated comment opened at 1:8"),
+        (
+            "let s = \"abc;\nputs(s)",
+            "unterminated string opened at 1:9",
+        ),
+        ("1 + \0 2", "illegal character '\\0' at 1:5"),
+        ("let x = 1;\nx + 1 = 2", "invalid assignment target at 2:1"),
+        ("a[0] -= 1", "invalid assignment target at 1:1"),
+        ("if (x) { break; }", "'break' outside loop at 1:10"),
+        (
+            "while (x) { fn() { continue } }",
+            "'continue' outside loop at 1:20",
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+        p.parse_program();
+
+        assert_eq!(format!("{}", p.errors[0]), expect);
+    }
+}
+
+#[test]
+fn test_parse_error_recovery() {
+    let input = r#"
+        let x 5;
+        let y = 10;
+        let = 3;
+        let z = (1 + 2;
+        fn(a, 1) { a };
+        let f = fn() { 1 + ; 2 };
+        let ok = 1;
+        }
+        if (ok) { ok } else { ok
+    "#;
+
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+
+    let errors: Vec<String> = p.errors.iter().map(|err| format!("{}", err)).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "expected next token to be ASSIGN, got INT(5) instead at 2:15",
+            "expected identifier, got ASSIGN instead at 4:13",
+            "expected next token to be RPAREN, got SEMICOLON instead at 5:23",
+            "expected identifier, got INT(1) instead at 6:15",
+            "unknown token in expression. got SEMICOLON at 7:28",
+            "unknown token in expression. got RBRACE at 9:9",
+            "unterminated block opened at 10:29",
+        ]
+    );
+
+    let stmts: Vec<String> = program
+        .statements
+        .iter()
+        .map(|stmt| format!("{}", stmt))
+        .collect();
+    assert_eq!(
+        stmts,
+        vec!["let y = 10", "let f = fn () {\n 2 }", "let ok = 1"]
+    );
+}
+
+#[test]
+fn test_parse_after_illegal_character() {
+    let input = "let x = 5 @ 3;\nlet y = 2;";
+
+    let mut l = Lexer::new(input);
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program();
+
+    assert_eq!(p.errors.len(), 1);
+    let stmts: Vec<String> = program
+        .statements
+        .iter()
+        .map(|stmt| format!("{}", stmt))
+        .collect();
+    assert_eq!(stmts, vec!["let x = 5", "let y = 2"]);
+}
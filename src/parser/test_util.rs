@@ -4,29 +4,64 @@ pub trait Literable: Sized {
     fn check_expr(self, expr: &Expr);
 
     fn check_stmt(self, stmt: &Stmt) {
-        if let Stmt::Expr(expr) = stmt {
+        if let StmtKind::Expr(expr) = &stmt.kind {
             self.check_expr(expr)
         } else {
-            panic!(format!("this stmt is not include expr. got {:?}", stmt));
+            panic!("this stmt is not include expr. got {:?}", stmt);
         }
     }
 }
 
 impl Literable for i64 {
     fn check_expr(self, expr: &Expr) {
-        assert_eq!(&Expr::Literal(Literal::Int(self)), expr, "got {}", expr);
+        assert_eq!(
+            ExprKind::Literal(Literal::Int(self)),
+            expr.kind,
+            "got {}",
+            expr
+        );
+    }
+}
+
+impl Literable for f64 {
+    fn check_expr(self, expr: &Expr) {
+        assert_eq!(
+            ExprKind::Literal(Literal::Float(self)),
+            expr.kind,
+            "got {}",
+            expr
+        );
     }
 }
 
 impl Literable for &str {
     fn check_expr(self, expr: &Expr) {
-        assert_eq!(&Expr::Ident(Ident(self.to_string())), expr, "got {}", expr);
+        assert_eq!(
+            ExprKind::Ident(Ident(self.to_string())),
+            expr.kind,
+            "got {}",
+            expr
+        );
     }
 }
 
 impl Literable for bool {
     fn check_expr(self, expr: &Expr) {
-        assert_eq!(&Expr::Literal(Literal::Bool(self)), expr, "got {}", expr);
+        assert_eq!(
+            ExprKind::Literal(Literal::Bool(self)),
+            expr.kind,
+            "got {}",
+            expr
+        );
+    }
+}
+
+/// unwrap the expression of expression statement
+pub fn expr_kind(stmt: &Stmt) -> &ExprKind {
+    if let StmtKind::Expr(expr) = &stmt.kind {
+        &expr.kind
+    } else {
+        panic!("this stmt is not expr. got {}", stmt);
     }
 }
 
@@ -44,12 +79,12 @@ pub fn check_infix_expr<T: Literable>(
     expected_infix: Infix,
     expected_right: T,
 ) {
-    if let Expr::Infix(box left, infix, box right) = expr {
+    if let ExprKind::Infix(box left, infix, box right) = &expr.kind {
         expected_left.check_expr(left);
         assert_eq!(expected_infix, *infix);
         expected_right.check_expr(right);
     } else {
-        panic!(format!("this expr is not 'infix'. got {}", expr));
+        panic!("this expr is not 'infix'. got {}", expr);
     }
 }
 
@@ -59,17 +94,17 @@ pub fn check_infix_stmt<T: Literable>(
     expect_infix: Infix,
     expect_right: T,
 ) {
-    if let Stmt::Expr(expr) = stmt {
+    if let StmtKind::Expr(expr) = &stmt.kind {
         check_infix_expr(expr, expect_left, expect_infix, expect_right)
     } else {
-        panic!(format!("this stmt is not expr. got {}", stmt));
+        panic!("this stmt is not expr. got {}", stmt);
     }
 }
 
 pub fn check_let_stmt(stmt: &Stmt, expected_name: &str) {
-    if let Stmt::Let(Ident(name), _) = stmt {
+    if let StmtKind::Let(Ident(name), _) = &stmt.kind {
         assert_eq!(expected_name, name)
     } else {
-        panic!(format!("this stmt is not 'let'. got {}", stmt));
+        panic!("this stmt is not 'let'. got {}", stmt);
     }
 }
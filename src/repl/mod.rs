@@ -1,46 +1,149 @@
-use crate::evaluator::eval;
+use crate::ast::Program;
 use crate::lexer::Lexer;
-use crate::object::Env;
-use crate::parser::Parser;
+use crate::parser::{ParseError, Parser};
+use crate::runner::{Config, Machine};
+use crate::token::Token;
 use std::io;
 
+#[cfg(test)]
+mod test;
+
 const PROMPT: &str = ">> ";
+const CONTINUATION_PROMPT: &str = ".. ";
 
-#[cfg_attr(tarpaulin, skip)]
-pub fn start<R, W>(mut reader: R, mut writer: W) -> io::Result<!>
+/// read, evaluate and print until `:quit` or the end of input
+pub fn start<R, W>(mut reader: R, mut writer: W, config: Config) -> io::Result<()>
 where
     R: io::BufRead,
     W: io::Write,
 {
-    let mut env = Env::new();
+    let mut machine = Machine::new(config);
     loop {
-        write!(writer, "{}", PROMPT)?;
-        writer.flush()?;
-        let mut line = String::new();
-        reader.read_line(&mut line)?;
-        if let Ok(mut lex) = Lexer::new(line) {
-            let mut p = Parser::new(&mut lex);
-            let program = p.parse_program();
-
-            if !p.errors.is_empty() {
-                writer = print_parse_errors(writer, p.errors)?;
+        let input = read_input(&mut reader, &mut writer)?;
+        if input.is_empty() {
+            writeln!(writer)?; // end of input. leave the prompt on its own line
+            return Ok(());
+        }
+
+        if let Some(command) = input.trim().strip_prefix(':') {
+            if !run_command(command, &mut machine, config, &mut writer)? {
+                return Ok(());
+            }
+            continue;
+        }
+
+        let program = match parse(&input) {
+            Ok(program) => program,
+            Err(errors) => {
+                print_parse_errors(&mut writer, errors)?;
                 continue;
             }
+        };
+
+        let val = machine.run(program);
+        match val {
+            Ok(val) => writeln!(writer, "{}", val)?,
+            Err(val) => writeln!(writer, "[ERROR] {}", val)?,
+        }
+    }
+}
+
+/// runs a command such as `:env` given without the colon. returns whether to keep going
+fn run_command<W: io::Write>(
+    command: &str,
+    machine: &mut Machine,
+    config: Config,
+    writer: &mut W,
+) -> io::Result<bool> {
+    let (name, arg) = match command.find(char::is_whitespace) {
+        Some(idx) => (&command[..idx], command[idx..].trim()),
+        None => (command, ""),
+    };
 
-            let val = eval(program, &mut env);
-            match val {
-                Ok(val) => writeln!(writer, "{}", val)?,
-                Err(val) => writeln!(writer, "[ERROR] {}", val)?,
+    match name {
+        "tokens" => {
+            let mut lex = Lexer::new(arg);
+            loop {
+                let (tok, span) = lex.next_token();
+                writeln!(writer, "{:?} at {}", tok, span)?;
+                if tok == Token::EOF {
+                    break;
+                }
             }
-        } else {
-            writeln!(writer, "[ERROR] please input only ASCII string").unwrap();
         }
+        "ast" => match parse(arg) {
+            Ok(program) => {
+                writeln!(writer, "{}", program)?;
+                writeln!(writer, "{:?}", program)?;
+            }
+            Err(errors) => print_parse_errors(writer, errors)?,
+        },
+        "env" => {
+            for (name, val) in machine.bindings() {
+                writeln!(writer, "{} = {}", name, val)?;
+            }
+        }
+        "reset" => *machine = Machine::new(config),
+        "quit" => return Ok(false),
+        _ => writeln!(
+            writer,
+            "[ERROR] unknown command :{}. available: :tokens, :ast, :env, :reset, :quit",
+            name
+        )?,
     }
+    Ok(true)
 }
 
-fn print_parse_errors<W: io::Write>(mut writer: W, errors: Vec<String>) -> io::Result<W> {
+fn parse(input: &str) -> Result<Program, Vec<ParseError>> {
+    let mut lex = Lexer::new(input);
+    let mut p = Parser::new(&mut lex);
+    let program = p.parse_program();
+
+    if p.errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(p.errors)
+    }
+}
+
+fn print_parse_errors<W: io::Write>(writer: &mut W, errors: Vec<ParseError>) -> io::Result<()> {
     for err in errors {
         writeln!(writer, "\t{}", err)?
     }
-    Ok(writer)
+    Ok(())
+}
+
+/// read lines until brackets opened in them are closed
+fn read_input<R, W>(reader: &mut R, writer: &mut W) -> io::Result<String>
+where
+    R: io::BufRead,
+    W: io::Write,
+{
+    let mut input = String::new();
+    loop {
+        let prompt = if input.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+        write!(writer, "{}", prompt)?;
+        writer.flush()?;
+        if reader.read_line(&mut input)? == 0 || is_complete(&input) {
+            return Ok(input);
+        }
+    }
+}
+
+/// whether no bracket or comment is left open. extra closing ones are left to the parser to report
+fn is_complete(input: &str) -> bool {
+    let mut depth = 0;
+    for tok in Lexer::new(input) {
+        match tok {
+            Token::LBRACE | Token::LPAREN | Token::LBRACKET => depth += 1,
+            Token::RBRACE | Token::RPAREN | Token::RBRACKET => depth -= 1,
+            Token::UNTERMINATEDCOMMENT | Token::UNTERMINATEDSTRING => return false,
+            _ => {}
+        }
+    }
+    depth <= 0
 }
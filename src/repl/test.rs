@@ -0,0 +1,93 @@
+use super::*;
+use crate::runner::Engine;
+
+#[test]
+fn test_is_complete() {
+    let test_cases = vec![
+        ("let x = 1;", true),
+        ("", true),
+        ("let f = fn(x) {", false),
+        ("let f = fn(x) {\n  x + 1\n};", true),
+        ("add(1,", false),
+        ("[1, [2, 3]", false),
+        ("\"{\"", true),
+        ("puts(\"a", false),
+        ("1 }", true),
+        ("1 /* {", false),
+        ("1 // {", true),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(is_complete(input), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_read_input() {
+    let mut reader = "let f = fn(x) {\n  x + 1\n};\nf(2)\n".as_bytes();
+    let mut writer = Vec::new();
+
+    let input = read_input(&mut reader, &mut writer).unwrap();
+    assert_eq!(input, "let f = fn(x) {\n  x + 1\n};\n");
+    assert_eq!(String::from_utf8(writer).unwrap(), ">> .. .. ");
+
+    let input = read_input(&mut reader, &mut Vec::new()).unwrap();
+    assert_eq!(input, "f(2)\n");
+}
+
+#[test]
+fn test_read_input_until_eof() {
+    let mut reader = "let f = fn(x) {\n".as_bytes();
+
+    let input = read_input(&mut reader, &mut Vec::new()).unwrap();
+    assert_eq!(input, "let f = fn(x) {\n");
+}
+
+fn run_session(input: &str, engine: Engine) -> String {
+    let mut output = Vec::new();
+    start(input.as_bytes(), &mut output, Config::new(engine)).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_start() {
+    let input = "let add = fn(a, b) {\n  a + b\n};\nadd(1, 2)\nlet x 1;\n";
+
+    for engine in &[Engine::Eval, Engine::Vm] {
+        assert_eq!(
+            run_session(input, *engine),
+            ">> .. .. null\n>> 3\n>> \texpected next token to be ASSIGN, got INT(1) instead at 1:7\n>> \n"
+        );
+    }
+}
+
+#[test]
+fn test_commands() {
+    let input = ":tokens x <= 1\n:ast -a * b\nlet x = 1;\nlet m = macro(a) { a };\n:env\n:reset\n:env\n:foo\n:quit\n1\n";
+
+    for engine in &[Engine::Eval, Engine::Vm] {
+        let output = run_session(input, *engine);
+        let lines: Vec<&str> = output.split(">> ").collect();
+        assert_eq!(
+            lines[1],
+            "IDENT(\"x\") at 1:1\nLTEQ at 1:3\nINT(1) at 1:6\nEOF at 1:7\n"
+        );
+        assert!(lines[2].starts_with("((-a) * b)\nProgram { statements: ["));
+        assert_eq!(lines[5], "m = macro (a) { a }\nx = 1\n");
+        assert_eq!(lines[7], "");
+        assert!(lines[8].starts_with("[ERROR] unknown command :foo."));
+        assert_eq!(lines.len(), 10, "{}", output);
+    }
+}
+
+#[test]
+fn test_start_after_error_in_function() {
+    let input = "let f = fn() { quote(1, 2) };\nlet x = 1;\nx\n";
+
+    for engine in &[Engine::Eval, Engine::Vm] {
+        let output = run_session(input, *engine);
+        let lines: Vec<&str> = output.split(">> ").collect();
+        // the VM reports the error when compiling the function, the evaluator when calling it
+        assert_eq!(&lines[2..], ["null\n", "1\n", "\n"], "{}", output);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::ast::Program;
+use crate::compiler::Compiler;
+use crate::evaluator::{define_macros, eval, expand_macros};
+use crate::lexer::Lexer;
+use crate::object::{Env, EvalError, EvalResult, Object, DEFAULT_MAX_CALL_DEPTH};
+use crate::parser::{ParseError, Parser};
+use crate::vm::Vm;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+/// how programs are executed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Engine {
+    /// walk the AST (see `crate::evaluator`)
+    Eval,
+    /// compile to bytecode and run on the virtual machine (see `crate::vm`)
+    Vm,
+}
+
+/// settings of a `Machine`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Config {
+    pub engine: Engine,
+    /// nested function calls allowed before reporting an error
+    pub max_call_depth: usize,
+}
+
+impl Config {
+    pub fn new(engine: Engine) -> Self {
+        Config {
+            engine,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+
+    /// native stack needed by a thread running programs. the evaluator
+    /// recurses on it for every function call
+    pub fn stack_size(&self) -> usize {
+        STACK_PER_CALL
+            .saturating_mul(self.max_call_depth)
+            .saturating_add(STACK_BASE)
+    }
+}
+
+// a call takes about 20KB when nested in a few expressions, and several times
+// more in debug builds. the stack is only reserved, not allocated up front
+const STACK_BASE: usize = 1024 * 1024;
+const STACK_PER_CALL: usize = if cfg!(debug_assertions) {
+    256 * 1024
+} else {
+    64 * 1024
+};
+
+/// state kept between programs, e.g. the lines of a REPL session
+pub struct Machine {
+    macro_env: Env,
+    backend: Backend,
+}
+
+enum Backend {
+    Eval(Env),
+    Vm(Box<Compiler>, Box<Vm>),
+}
+
+impl Machine {
+    pub fn new(config: Config) -> Self {
+        let max_call_depth = config.max_call_depth;
+        let backend = match config.engine {
+            Engine::Eval => Backend::Eval(Env::with_max_call_depth(max_call_depth)),
+            Engine::Vm => Backend::Vm(
+                Box::default(),
+                Box::new(Vm::with_max_call_depth(max_call_depth)),
+            ),
+        };
+        Machine {
+            macro_env: Env::with_max_call_depth(max_call_depth),
+            backend,
+        }
+    }
+
+    /// expand macros in the program, then run it
+    pub fn run(&mut self, mut program: Program) -> EvalResult<Object> {
+        define_macros(&mut program, &self.macro_env);
+        let program = expand_macros(program, &self.macro_env)?;
+
+        match &mut self.backend {
+            Backend::Eval(env) => eval(program, env),
+            Backend::Vm(compiler, vm) => vm.run(compiler.compile(&program)?),
+        }
+    }
+
+    /// variables and macros defined by the programs run so far
+    pub fn bindings(&self) -> Vec<(String, Object)> {
+        let mut bindings = match &self.backend {
+            Backend::Eval(env) => env.bindings(),
+            Backend::Vm(_, vm) => vm.globals(),
+        };
+        bindings.extend(self.macro_env.bindings());
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+}
+
+/// reasons why a script failed to run
+#[derive(Debug)]
+pub enum RunError {
+    Io(io::Error),
+    Parse(Vec<ParseError>),
+    Eval(EvalError),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Io(err) => write!(f, "[ERROR] failed to read script: {}", err),
+            RunError::Parse(errors) => {
+                writeln!(f, "[ERROR] failed to parse script")?;
+                let errors: Vec<String> = errors.iter().map(|err| format!("\t{}", err)).collect();
+                write!(f, "{}", errors.join("\n"))
+            }
+            RunError::Eval(err) => write!(f, "[ERROR] {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for RunError {
+    fn from(err: io::Error) -> Self {
+        RunError::Io(err)
+    }
+}
+
+pub fn run_file(path: &Path, config: Config) -> Result<Object, RunError> {
+    let source = fs::read_to_string(path)?;
+    run(source, config)
+}
+
+/// lex, parse and evaluate the whole source in a fresh environment
+pub fn run(source: String, config: Config) -> Result<Object, RunError> {
+    let source = strip_shebang(source);
+    let mut lex = Lexer::new(&source);
+    let mut p = Parser::new(&mut lex);
+    let program = p.parse_program();
+
+    if !p.errors.is_empty() {
+        return Err(RunError::Parse(p.errors));
+    }
+
+    Machine::new(config).run(program).map_err(RunError::Eval)
+}
+
+/// blank out `#!` line but keep its newline so that locations in errors stay correct
+fn strip_shebang(source: String) -> String {
+    if source.starts_with("#!") {
+        match source.find('\n') {
+            Some(idx) => source[idx..].to_string(),
+            None => String::new(),
+        }
+    } else {
+        source
+    }
+}
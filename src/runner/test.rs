@@ -0,0 +1,107 @@
+use super::*;
+use std::env;
+use std::thread;
+
+#[test]
+fn test_run() {
+    let source = "let add = fn(x, y) { x + y };\nadd(1, 2)".to_string();
+
+    assert_eq!(
+        run(source.clone(), Config::new(Engine::Eval)).unwrap(),
+        Object::Int(3)
+    );
+    assert_eq!(
+        run(source, Config::new(Engine::Vm)).unwrap(),
+        Object::Int(3)
+    );
+}
+
+#[test]
+fn test_run_with_shebang() {
+    let source = "#!/usr/bin/env monkey_lang\nlet x = 1;\nx + true".to_string();
+
+    for engine in &[Engine::Eval, Engine::Vm] {
+        match run(source.clone(), Config::new(*engine)) {
+            Err(RunError::Eval(err)) => {
+                assert_eq!(format!("{}", err), "type mismatch: INT + BOOLEAN at 3:1")
+            }
+            result => panic!("expected evaluation error. got {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn test_run_with_macros() {
+    let source = r#"
+    let unless = macro(cond, cons, alt) {
+        quote(if (!(unquote(cond))) { unquote(cons) } else { unquote(alt) })
+    };
+    unless(10 > 5, 1, 2)
+    "#;
+
+    for engine in &[Engine::Eval, Engine::Vm] {
+        assert_eq!(
+            run(source.to_string(), Config::new(*engine)).unwrap(),
+            Object::Int(2)
+        );
+    }
+}
+
+#[test]
+fn test_run_parse_error() {
+    let source = "let x 1;\nlet y = 2;".to_string();
+
+    match run(source, Config::new(Engine::Eval)) {
+        Err(RunError::Parse(errors)) => assert_eq!(errors.len(), 1),
+        result => panic!("expected parse error. got {:?}", result),
+    }
+}
+
+#[test]
+fn test_run_file() {
+    let path = env::temp_dir().join("monkey_lang_test_run_file.mk");
+    fs::write(&path, "#!/usr/bin/env monkey_lang\nlen(\"monkey\")").unwrap();
+
+    let result = run_file(&path, Config::new(Engine::Eval));
+    fs::remove_file(&path).unwrap();
+    assert_eq!(result.unwrap(), Object::Int(6));
+
+    assert!(matches!(
+        run_file(Path::new("no/such/script.mk"), Config::new(Engine::Eval)),
+        Err(RunError::Io(_))
+    ));
+}
+
+#[test]
+fn test_run_deep_recursion() {
+    let source = "let f = fn(n) { if (true) { [{\"a\": 1 + f(n + 1)}[\"a\"]][0] } };\nf(0)";
+
+    for engine in &[Engine::Eval, Engine::Vm] {
+        let config = Config::new(*engine);
+        let result = thread::Builder::new()
+            .stack_size(config.stack_size())
+            .spawn(move || format!("{}", run(source.to_string(), config).unwrap_err()))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(
+            result,
+            "[ERROR] maximum call depth (10000) exceeded in f at 1:40"
+        );
+    }
+}
+
+#[test]
+fn test_machine_after_call_depth_exceeded() {
+    let parse = |source: &str| Parser::new(&mut Lexer::new(source)).parse_program();
+
+    for engine in &[Engine::Eval, Engine::Vm] {
+        let mut machine = Machine::new(Config {
+            engine: *engine,
+            max_call_depth: 10,
+        });
+        let source = "let f = fn(n) { if (n > 1) { 1 + f(n - 1) } else { 1 } }; f(11)";
+        assert!(machine.run(parse(source)).is_err());
+        assert_eq!(machine.run(parse("f(10)")), Ok(Object::Int(10)));
+    }
+}
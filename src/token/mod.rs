@@ -1,77 +1,221 @@
+use num_bigint::BigInt;
+use std::borrow::Cow;
 use std::fmt;
 
+/// `IDENT` and `STRING` borrow their text from the source while lexing
+/// (see `Token::into_owned`)
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Clone, Debug)]
-pub enum Token {
+pub enum Token<'a> {
     // Special token
     EOF,
+    ILLEGAL(String),
+    UNTERMINATEDCOMMENT, // `/*` without matching `*/`
+    UNTERMINATEDSTRING,  // `"` without closing one
 
     // Identifiers + literals
-    IDENT(String),
+    IDENT(Cow<'a, str>),
     INT(i64),
+    BIGINT(BigInt), // integer literal too large for `INT`
+    FLOAT(f64),
+    STRING(Cow<'a, str>),
 
     // Operators
     ASSIGN,
+    PLUSASSIGN,
+    MINUSASSIGN,
+    ASTERISKASSIGN,
+    SLASHASSIGN,
     PLUS,
     MINUS,
     BANG,
     ASTERISK,
+    POWER,
+    PERCENT,
     SLASH,
 
     LT,
     GT,
+    LTEQ,
+    GTEQ,
 
     EQ,
     NOTEQ,
+    AND,
+    OR,
 
     // Delimiters
     COMMA,
     SEMICOLON,
+    COLON,
 
     LPAREN,
     RPAREN,
     LBRACE,
     RBRACE,
+    LBRACKET,
+    RBRACKET,
 
     // Keywords
     FUNCTION,
+    MACRO,
     LET,
     TRUE,
     FALSE,
     IF,
     ELSE,
     RETURN,
+    WHILE,
+    BREAK,
+    CONTINUE,
 }
 
 #[cfg_attr(tarpaulin, skip)]
-impl fmt::Display for Token {
+impl Token<'_> {
+    /// copy of the token not borrowing from the source, e.g. to keep it in an error
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::IDENT(val) => Token::IDENT(Cow::Owned(val.into_owned())),
+            Token::STRING(val) => Token::STRING(Cow::Owned(val.into_owned())),
+            Token::EOF => Token::EOF,
+            Token::ILLEGAL(val) => Token::ILLEGAL(val),
+            Token::UNTERMINATEDCOMMENT => Token::UNTERMINATEDCOMMENT,
+            Token::UNTERMINATEDSTRING => Token::UNTERMINATEDSTRING,
+            Token::INT(val) => Token::INT(val),
+            Token::BIGINT(val) => Token::BIGINT(val),
+            Token::FLOAT(val) => Token::FLOAT(val),
+            Token::ASSIGN => Token::ASSIGN,
+            Token::PLUSASSIGN => Token::PLUSASSIGN,
+            Token::MINUSASSIGN => Token::MINUSASSIGN,
+            Token::ASTERISKASSIGN => Token::ASTERISKASSIGN,
+            Token::SLASHASSIGN => Token::SLASHASSIGN,
+            Token::PLUS => Token::PLUS,
+            Token::MINUS => Token::MINUS,
+            Token::BANG => Token::BANG,
+            Token::ASTERISK => Token::ASTERISK,
+            Token::POWER => Token::POWER,
+            Token::PERCENT => Token::PERCENT,
+            Token::SLASH => Token::SLASH,
+            Token::LT => Token::LT,
+            Token::GT => Token::GT,
+            Token::LTEQ => Token::LTEQ,
+            Token::GTEQ => Token::GTEQ,
+            Token::EQ => Token::EQ,
+            Token::NOTEQ => Token::NOTEQ,
+            Token::AND => Token::AND,
+            Token::OR => Token::OR,
+            Token::COMMA => Token::COMMA,
+            Token::SEMICOLON => Token::SEMICOLON,
+            Token::COLON => Token::COLON,
+            Token::LPAREN => Token::LPAREN,
+            Token::RPAREN => Token::RPAREN,
+            Token::LBRACE => Token::LBRACE,
+            Token::RBRACE => Token::RBRACE,
+            Token::LBRACKET => Token::LBRACKET,
+            Token::RBRACKET => Token::RBRACKET,
+            Token::FUNCTION => Token::FUNCTION,
+            Token::MACRO => Token::MACRO,
+            Token::LET => Token::LET,
+            Token::TRUE => Token::TRUE,
+            Token::FALSE => Token::FALSE,
+            Token::IF => Token::IF,
+            Token::ELSE => Token::ELSE,
+            Token::RETURN => Token::RETURN,
+            Token::WHILE => Token::WHILE,
+            Token::BREAK => Token::BREAK,
+            Token::CONTINUE => Token::CONTINUE,
+        }
+    }
+}
+
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Token::EOF => write!(f, "EOF"),
+            Token::ILLEGAL(val) => write!(f, "{}", val),
+            Token::UNTERMINATEDCOMMENT => write!(f, "/*"),
+            Token::UNTERMINATEDSTRING => write!(f, "\""),
             Token::IDENT(val) => write!(f, "{}", val),
             Token::INT(val) => write!(f, "{}", val),
+            Token::BIGINT(val) => write!(f, "{}", val),
+            Token::FLOAT(val) => write!(f, "{:?}", val),
+            Token::STRING(val) => write!(f, "\"{}\"", val),
             Token::ASSIGN => write!(f, "="),
+            Token::PLUSASSIGN => write!(f, "+="),
+            Token::MINUSASSIGN => write!(f, "-="),
+            Token::ASTERISKASSIGN => write!(f, "*="),
+            Token::SLASHASSIGN => write!(f, "/="),
             Token::PLUS => write!(f, "+"),
             Token::MINUS => write!(f, "-"),
             Token::BANG => write!(f, "!"),
             Token::ASTERISK => write!(f, "*"),
+            Token::POWER => write!(f, "**"),
+            Token::PERCENT => write!(f, "%"),
             Token::SLASH => write!(f, "/"),
-            Token::LT => write!(f, ">"),
-            Token::GT => write!(f, "<"),
+            Token::LT => write!(f, "<"),
+            Token::GT => write!(f, ">"),
+            Token::LTEQ => write!(f, "<="),
+            Token::GTEQ => write!(f, ">="),
             Token::EQ => write!(f, "=="),
             Token::NOTEQ => write!(f, "!="),
+            Token::AND => write!(f, "&&"),
+            Token::OR => write!(f, "||"),
             Token::COMMA => write!(f, ","),
             Token::SEMICOLON => write!(f, ";"),
+            Token::COLON => write!(f, ":"),
             Token::LPAREN => write!(f, "("),
             Token::RPAREN => write!(f, ")"),
             Token::LBRACE => write!(f, "{{"),
             Token::RBRACE => write!(f, "}}"),
+            Token::LBRACKET => write!(f, "["),
+            Token::RBRACKET => write!(f, "]"),
             Token::FUNCTION => write!(f, "fn"),
+            Token::MACRO => write!(f, "macro"),
             Token::LET => write!(f, "let"),
             Token::TRUE => write!(f, "true"),
             Token::FALSE => write!(f, "false"),
             Token::IF => write!(f, "if"),
             Token::ELSE => write!(f, "else"),
             Token::RETURN => write!(f, "return"),
+            Token::WHILE => write!(f, "while"),
+            Token::BREAK => write!(f, "break"),
+            Token::CONTINUE => write!(f, "continue"),
         }
     }
 }
+
+/// location of a character in source (1-origin)
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// range of source from `start` to `end` (exclusive)
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Span {
+    pub start: Pos,
+    pub end: Pos,
+}
+
+impl Span {
+    /// span covering from the start of `self` to the end of `other`
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.start)
+    }
+}
@@ -0,0 +1,405 @@
+use crate::ast::{Infix, Prefix};
+use crate::builtins;
+use crate::code::{read_u16, Opcode};
+use crate::compiler::Bytecode;
+use crate::evaluator::quote::quote;
+use crate::evaluator::{
+    call_depth_error, check_arity, eval_index_expr, eval_infix_expr, eval_prefix_expr, is_truthy,
+    to_hash_key, undeclared_assignment_error,
+};
+use crate::object::{
+    Closure, CompiledFunction, EvalError, EvalResult, Object, DEFAULT_MAX_CALL_DEPTH,
+};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod test;
+
+struct Frame {
+    closure: Rc<Closure>,
+    ip: usize,
+    base_pointer: usize,
+}
+
+/// stack machine executing `Bytecode`. globals are kept across calls of `run`
+/// so it can be fed line by line from the REPL
+pub struct Vm {
+    constants: Vec<Object>,
+    globals: Vec<Option<Object>>,
+    global_names: Vec<String>,
+    stack: Vec<Object>,
+    frames: Vec<Frame>,
+    max_call_depth: usize,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::with_max_call_depth(DEFAULT_MAX_CALL_DEPTH)
+    }
+}
+
+impl Vm {
+    /// machine allowing at most `max_call_depth` nested function calls, like the evaluator
+    pub fn with_max_call_depth(max_call_depth: usize) -> Self {
+        Vm {
+            constants: Vec::new(),
+            globals: Vec::new(),
+            global_names: Vec::new(),
+            stack: Vec::new(),
+            frames: Vec::new(),
+            max_call_depth,
+        }
+    }
+
+    /// runs program and returns the value of its last expression statement
+    pub fn run(&mut self, bytecode: Bytecode) -> EvalResult<Object> {
+        self.constants = bytecode.constants;
+        self.global_names = bytecode.global_names;
+        self.stack.clear();
+
+        let main = CompiledFunction {
+            name: None,
+            instructions: bytecode.instructions,
+            spans: bytecode.spans,
+            num_locals: 0,
+            num_params: 0,
+            repr: String::new(),
+        };
+        self.frames = vec![Frame {
+            closure: Rc::new(Closure {
+                func: Rc::new(main),
+                free: Vec::new(),
+            }),
+            ip: 0,
+            base_pointer: 0,
+        }];
+
+        let mut last_popped = Object::Null;
+        loop {
+            let frame = self.current_frame();
+            let ins = &frame.closure.func.instructions;
+            if frame.ip >= ins.len() {
+                return Ok(last_popped);
+            }
+
+            let op_ip = frame.ip;
+            let op = Opcode::from_byte(ins[op_ip]);
+            self.current_frame_mut().ip += 1;
+
+            match self.execute(op, &mut last_popped) {
+                Ok(Some(returned)) => return Ok(returned),
+                Ok(None) => {}
+                Err(err) => {
+                    let span = self.current_frame().closure.func.spans.lookup(op_ip);
+                    return Err(match span {
+                        Some(span) => err.or_span(span),
+                        None => err,
+                    });
+                }
+            }
+        }
+    }
+
+    /// executes one instruction. returns a value when the program halts with `return`
+    fn execute(&mut self, op: Opcode, last_popped: &mut Object) -> EvalResult<Option<Object>> {
+        match op {
+            Opcode::Constant => {
+                let idx = self.read_u16_operand();
+                self.push(self.constants[idx].clone());
+            }
+            Opcode::Pop => *last_popped = self.pop(),
+            Opcode::True => self.push(Object::Bool(true)),
+            Opcode::False => self.push(Object::Bool(false)),
+            Opcode::Null => self.push(Object::Null),
+            Opcode::Add
+            | Opcode::Sub
+            | Opcode::Mul
+            | Opcode::Div
+            | Opcode::Equal
+            | Opcode::NotEqual
+            | Opcode::GreaterThan
+            | Opcode::LessThan
+            | Opcode::Mod
+            | Opcode::Pow
+            | Opcode::GreaterEqual
+            | Opcode::LessEqual => {
+                let right = self.pop();
+                let left = self.pop();
+                self.push(eval_infix_expr(to_infix(op), left, right)?);
+            }
+            Opcode::Minus | Opcode::Bang | Opcode::UnaryPlus => {
+                let prefix = match op {
+                    Opcode::Minus => Prefix::Minus,
+                    Opcode::Bang => Prefix::Not,
+                    _ => Prefix::Plus,
+                };
+                let right = self.pop();
+                self.push(eval_prefix_expr(prefix, right)?);
+            }
+            Opcode::Jump => {
+                let target = self.read_u16_operand();
+                self.current_frame_mut().ip = target;
+            }
+            Opcode::JumpNotTruthy => {
+                let target = self.read_u16_operand();
+                if !is_truthy(&self.pop()) {
+                    self.current_frame_mut().ip = target;
+                }
+            }
+            Opcode::GetGlobal => {
+                let idx = self.read_u16_operand();
+                let obj = self.get_global(idx)?;
+                self.push(obj);
+            }
+            Opcode::SetGlobal => {
+                let idx = self.read_u16_operand();
+                if self.globals.len() <= idx {
+                    self.globals.resize(idx + 1, None);
+                }
+                self.globals[idx] = Some(self.pop());
+            }
+            Opcode::AssignGlobal => {
+                let idx = self.read_u16_operand();
+                match self.globals.get_mut(idx) {
+                    Some(Some(obj)) => *obj = self.stack.pop().expect("stack underflow"),
+                    _ => return Err(undeclared_assignment_error(&self.global_names[idx])),
+                }
+            }
+            Opcode::GetLocal => {
+                let idx = self.current_frame().base_pointer + self.read_u8_operand();
+                self.push(deref_cell(&self.stack[idx]));
+            }
+            Opcode::SetLocal => {
+                let idx = self.current_frame().base_pointer + self.read_u8_operand();
+                let val = self.pop();
+                match &self.stack[idx] {
+                    Object::Cell(cell) => *cell.borrow_mut() = val,
+                    _ => self.stack[idx] = val,
+                }
+            }
+            Opcode::GetFree => {
+                let idx = self.read_u8_operand();
+                let obj = deref_cell(&self.current_frame().closure.free[idx]);
+                self.push(obj);
+            }
+            Opcode::SetFree => {
+                let idx = self.read_u8_operand();
+                let val = self.pop();
+                match &self.current_frame().closure.free[idx] {
+                    Object::Cell(cell) => *cell.borrow_mut() = val,
+                    obj => panic!("free variable not in a cell: {}", obj),
+                }
+            }
+            Opcode::CaptureLocal => {
+                let idx = self.current_frame().base_pointer + self.read_u8_operand();
+                self.push(self.stack[idx].clone());
+            }
+            Opcode::CaptureFree => {
+                let idx = self.read_u8_operand();
+                let obj = self.current_frame().closure.free[idx].clone();
+                self.push(obj);
+            }
+            Opcode::MakeCell => {
+                let idx = self.current_frame().base_pointer + self.read_u8_operand();
+                if !matches!(self.stack[idx], Object::Cell(_)) {
+                    let val = std::mem::replace(&mut self.stack[idx], Object::Null);
+                    self.stack[idx] = Object::Cell(Rc::new(RefCell::new(val)));
+                }
+            }
+            Opcode::CurrentClosure => {
+                let closure = self.current_frame().closure.clone();
+                self.push(Object::Closure(closure));
+            }
+            Opcode::Array => {
+                let len = self.read_u16_operand();
+                let elements = self.stack.split_off(self.stack.len() - len);
+                self.push(Object::Array(elements));
+            }
+            Opcode::Hash => {
+                let len = self.read_u16_operand();
+                let mut hash = BTreeMap::new();
+                let mut items = self.stack.split_off(self.stack.len() - len).into_iter();
+                while let (Some(key), Some(val)) = (items.next(), items.next()) {
+                    hash.insert(to_hash_key(key)?, val);
+                }
+                self.push(Object::Hash(hash));
+            }
+            Opcode::Index => {
+                let index = self.pop();
+                let left = self.pop();
+                self.push(eval_index_expr(left, index)?);
+            }
+            Opcode::Call => {
+                let num_args = self.read_u8_operand();
+                self.call(num_args)?;
+            }
+            Opcode::ReturnValue => {
+                let val = self.pop();
+                let frame = self.frames.pop().expect("no frame to return from");
+                if self.frames.is_empty() {
+                    return Ok(Some(val));
+                }
+                // drops locals and the callee
+                self.stack.truncate(frame.base_pointer - 1);
+                self.push(val);
+            }
+            Opcode::Quote => {
+                let idx = self.read_u16_operand();
+                let num_unquoted = self.read_u8_operand();
+                let template = match &self.constants[idx] {
+                    Object::Quote(expr) => expr.clone(),
+                    obj => panic!("not a quote constant: {}", obj),
+                };
+                let mut unquoted = self
+                    .stack
+                    .split_off(self.stack.len() - num_unquoted)
+                    .into_iter();
+                let quoted = quote(template, |_| Ok(unquoted.next().expect("unquoted value")))?;
+                self.push(quoted);
+            }
+            Opcode::Closure => {
+                let idx = self.read_u16_operand();
+                let num_free = self.read_u8_operand();
+                let func = match &self.constants[idx] {
+                    Object::CompiledFunction(func) => func.clone(),
+                    obj => panic!("not a function constant: {}", obj),
+                };
+                let free = self.stack.split_off(self.stack.len() - num_free);
+                self.push(Object::Closure(Rc::new(Closure { func, free })));
+            }
+        }
+        Ok(None)
+    }
+
+    fn call(&mut self, num_args: usize) -> EvalResult<()> {
+        let mut callee_pos = self.stack.len() - 1 - num_args;
+        match self.stack[callee_pos].clone() {
+            Object::Closure(closure) => {
+                check_arity(closure.func.num_params, num_args)?;
+                if self.is_tail_call() {
+                    // replace the frame of the caller, whose result is the one of this call
+                    let frame = self.frames.pop().expect("no frame");
+                    self.stack.drain(frame.base_pointer - 1..callee_pos);
+                    callee_pos = frame.base_pointer - 1;
+                } else if self.frames.len() > self.max_call_depth {
+                    // the main program has a frame too
+                    let name = closure.func.name.as_deref();
+                    return Err(call_depth_error(self.max_call_depth, name));
+                }
+
+                let base_pointer = callee_pos + 1;
+                self.stack
+                    .resize(base_pointer + closure.func.num_locals, Object::Null);
+                self.frames.push(Frame {
+                    closure,
+                    ip: 0,
+                    base_pointer,
+                });
+            }
+            Object::Builtin(builtin) => {
+                let args = self.stack.split_off(callee_pos + 1);
+                self.stack.pop();
+                self.push((builtin.func)(args)?);
+            }
+            obj => {
+                return Err(EvalError::new(format!("'{}' is not function object", obj)));
+            }
+        }
+        Ok(())
+    }
+
+    /// whether the call just read returns its value from the current function directly
+    fn is_tail_call(&self) -> bool {
+        if self.frames.len() < 2 {
+            return false; // main program
+        }
+
+        let frame = self.current_frame();
+        let ins = &frame.closure.func.instructions;
+        let mut ip = frame.ip;
+        loop {
+            match Opcode::from_byte(ins[ip]) {
+                Opcode::ReturnValue => return true,
+                Opcode::Jump => ip = read_u16(&ins[ip + 1..]),
+                _ => return false,
+            }
+        }
+    }
+
+    /// global variables defined so far, in the order of definition
+    pub fn globals(&self) -> Vec<(String, Object)> {
+        self.global_names
+            .iter()
+            .zip(&self.globals)
+            .filter_map(|(name, val)| Some((name.clone(), val.clone()?)))
+            .collect()
+    }
+
+    fn get_global(&self, idx: usize) -> EvalResult<Object> {
+        if let Some(Some(obj)) = self.globals.get(idx) {
+            return Ok(obj.clone());
+        }
+
+        let name = &self.global_names[idx];
+        builtins::lookup(name)
+            .ok_or_else(|| EvalError::new(format!("identifier not found: {}", name)))
+    }
+
+    fn current_frame(&self) -> &Frame {
+        self.frames.last().expect("no frame")
+    }
+
+    fn current_frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("no frame")
+    }
+
+    fn read_u16_operand(&mut self) -> usize {
+        let frame = self.current_frame_mut();
+        let operand = read_u16(&frame.closure.func.instructions[frame.ip..]);
+        frame.ip += 2;
+        operand
+    }
+
+    fn read_u8_operand(&mut self) -> usize {
+        let frame = self.current_frame_mut();
+        let operand = frame.closure.func.instructions[frame.ip] as usize;
+        frame.ip += 1;
+        operand
+    }
+
+    fn push(&mut self, obj: Object) {
+        self.stack.push(obj)
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().expect("stack underflow")
+    }
+}
+
+/// value of a variable, which may be kept in a cell
+fn deref_cell(obj: &Object) -> Object {
+    match obj {
+        Object::Cell(cell) => cell.borrow().clone(),
+        obj => obj.clone(),
+    }
+}
+
+fn to_infix(op: Opcode) -> Infix {
+    match op {
+        Opcode::Add => Infix::Plus,
+        Opcode::Sub => Infix::Minus,
+        Opcode::Mul => Infix::Multiply,
+        Opcode::Div => Infix::Divide,
+        Opcode::Equal => Infix::Equal,
+        Opcode::NotEqual => Infix::NotEqual,
+        Opcode::GreaterThan => Infix::GreaterThan,
+        Opcode::LessThan => Infix::LessThan,
+        Opcode::Mod => Infix::Modulo,
+        Opcode::Pow => Infix::Power,
+        Opcode::GreaterEqual => Infix::GreaterOrEqual,
+        Opcode::LessEqual => Infix::LessOrEqual,
+        _ => unreachable!("not an infix opcode: {:?}", op),
+    }
+}
@@ -0,0 +1,70 @@
+use super::*;
+use crate::compiler::Compiler;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+fn run_lines(lines: &[&str]) -> Vec<EvalResult<Object>> {
+    let mut compiler = Compiler::new();
+    let mut vm = Vm::default();
+
+    lines
+        .iter()
+        .map(|line| {
+            let mut l = Lexer::new(line);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program();
+            assert!(p.errors.is_empty(), "{:?}", p.errors);
+            vm.run(compiler.compile(&program)?)
+        })
+        .collect()
+}
+
+fn test_run(input: &str) -> EvalResult<Object> {
+    run_lines(&[input]).pop().unwrap()
+}
+
+#[test]
+fn test_globals_kept_between_runs() {
+    let results = run_lines(&[
+        "let a = 1;",
+        "let f = fn(x) { x + a + b };",
+        "let b = 2;",
+        "f(3)",
+    ]);
+
+    assert_eq!(results[3], Ok(Object::Int(6)));
+}
+
+#[test]
+fn test_top_level_return() {
+    assert_eq!(test_run("if (true) { return 1; }; 2"), Ok(Object::Int(1)));
+}
+
+#[test]
+fn test_closures() {
+    let test_cases = vec![
+        (
+            "let adder = fn(a) { fn(b) { fn(c) { a + b + c } } }; adder(1)(2)(3)",
+            6,
+        ),
+        (
+            "let count = fn(x) { if (x == 0) { 0 } else { 1 + count(x - 1) } }; count(100)",
+            100,
+        ),
+        (
+            "let wrap = fn() { let inner = fn(x) { if (x == 0) { 0 } else { inner(x - 1) } }; inner(5) }; wrap()",
+            0,
+        ),
+    ];
+
+    for (input, expect) in test_cases {
+        assert_eq!(test_run(input), Ok(Object::Int(expect)));
+    }
+}
+
+#[test]
+fn test_error_location_in_function() {
+    let err = test_run("let f = fn(x) {\n  x + true\n};\nf(1)").unwrap_err();
+
+    assert_eq!(format!("{}", err), "type mismatch: INT + BOOLEAN at 2:3");
+}